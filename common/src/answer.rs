@@ -0,0 +1,447 @@
+//! A single answer type for every day, so that sums which outgrow their usual integer type get
+//! promoted to a bigger one instead of quietly wrapping around.
+//!
+//! Numeric answers are always kept in the narrowest variant that fits: `I128` if possible, then
+//! `U128`, and only then `Big`. That way two answers holding the same number always compare equal.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    I128(i128),
+    /// Only used for numbers too big for an `i128`
+    U128(u128),
+    /// Only used for numbers which don't fit in either of the above
+    Big(BigInt),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnswerError {
+    /// Tried to do arithmetic with a text answer
+    NotANumber(String),
+    /// Some fixed-width intermediate value (rather than the answer itself) overflowed
+    Overflow(String),
+}
+impl fmt::Display for AnswerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber(s) => write!(f, "Can't do arithmetic with the text answer {s:?}"),
+            Self::Overflow(what) => write!(f, "Overflowed while working out {what}"),
+        }
+    }
+}
+impl std::error::Error for AnswerError {}
+impl From<AnswerError> for std::io::Error {
+    fn from(e: AnswerError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl Answer {
+    pub fn zero() -> Self {
+        Self::I128(0)
+    }
+
+    pub fn one() -> Self {
+        Self::I128(1)
+    }
+
+    fn to_big(&self) -> Result<BigInt, AnswerError> {
+        match self {
+            Self::I128(n) => Ok(BigInt::from(*n)),
+            Self::U128(n) => Ok(BigInt::from(*n)),
+            Self::Big(n) => Ok(n.clone()),
+            Self::Text(s) => Err(AnswerError::NotANumber(s.clone())),
+        }
+    }
+
+    /// Adds two numeric answers, promoting to a wider variant if the result needs it
+    pub fn checked_add(&self, rhs: impl Into<Answer>) -> Result<Answer, AnswerError> {
+        let rhs = rhs.into();
+        if let (Self::I128(l), Self::I128(r)) = (self, &rhs) {
+            if let Some(sum) = l.checked_add(*r) {
+                return Ok(Self::I128(sum));
+            }
+        }
+        Ok(Self::from(&self.to_big()? + &rhs.to_big()?))
+    }
+
+    /// Multiplies two numeric answers, promoting to a wider variant if the result needs it
+    pub fn checked_mul(&self, rhs: impl Into<Answer>) -> Result<Answer, AnswerError> {
+        let rhs = rhs.into();
+        if let (Self::I128(l), Self::I128(r)) = (self, &rhs) {
+            if let Some(product) = l.checked_mul(*r) {
+                return Ok(Self::I128(product));
+            }
+        }
+        Ok(Self::from(&self.to_big()? * &rhs.to_big()?))
+    }
+
+    pub fn checked_pow(&self, mut exp: u32) -> Result<Answer, AnswerError> {
+        // Good old square-and-multiply
+        let mut base = self.clone();
+        let mut result = Self::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.checked_mul(base.clone())?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.checked_mul(base.clone())?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Adds up a whole bunch of numbers, stopping at the first one that isn't a number
+    pub fn sum<T: Into<Answer>>(items: impl IntoIterator<Item = T>) -> Result<Answer, AnswerError> {
+        items
+            .into_iter()
+            .try_fold(Self::zero(), |acc, item| acc.checked_add(item))
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::I128(n) => n.fmt(f),
+            Self::U128(n) => n.fmt(f),
+            Self::Big(n) => n.fmt(f),
+            Self::Text(s) => s.fmt(f),
+        }
+    }
+}
+
+macro_rules! answer_from_small_int {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Self {
+                Self::I128(n.into())
+            }
+        })*
+    };
+}
+answer_from_small_int!(u8, u16, u32, u64, i8, i16, i32, i64, i128);
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Self::from(n as u128)
+    }
+}
+impl From<u128> for Answer {
+    fn from(n: u128) -> Self {
+        match i128::try_from(n) {
+            Ok(n) => Self::I128(n),
+            Err(_) => Self::U128(n),
+        }
+    }
+}
+impl From<BigInt> for Answer {
+    fn from(n: BigInt) -> Self {
+        if let Some(small) = n.to_i128() {
+            Self::I128(small)
+        } else if let Some(small) = n.to_u128() {
+            Self::U128(small)
+        } else {
+            Self::Big(n)
+        }
+    }
+}
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Self::Text(s.to_string())
+    }
+}
+
+/// An arbitrary-precision integer. Only does what the answers need: adding, multiplying,
+/// comparing, and converting to and from decimal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    /// Base 2^32 digits, least significant first, with no trailing zeros (so zero is empty)
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        // There's no such thing as negative zero
+        let negative = negative && !magnitude.is_empty();
+        Self {
+            negative,
+            magnitude,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    fn magnitude_u128(&self) -> Option<u128> {
+        if self.magnitude.len() > 4 {
+            return None;
+        }
+        Some(
+            self.magnitude
+                .iter()
+                .rev()
+                .fold(0, |acc, &digit| (acc << 32) | digit as u128),
+        )
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.negative {
+            None
+        } else {
+            self.magnitude_u128()
+        }
+    }
+
+    pub fn to_i128(&self) -> Option<i128> {
+        let magnitude = self.magnitude_u128()?;
+        if self.negative {
+            0_i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+}
+
+fn cmp_magnitudes(l: &[u32], r: &[u32]) -> Ordering {
+    l.len()
+        .cmp(&r.len())
+        .then_with(|| l.iter().rev().cmp(r.iter().rev()))
+}
+
+fn add_magnitudes(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(l.len().max(r.len()) + 1);
+    let mut carry = 0;
+    for i in 0..l.len().max(r.len()) {
+        let sum = *l.get(i).unwrap_or(&0) as u64 + *r.get(i).unwrap_or(&0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    out.push(carry as u32);
+    out
+}
+
+/// `l - r`, where `l` must be at least as big as `r`
+fn sub_magnitudes(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(l.len());
+    let mut borrow = 0;
+    for (i, &digit) in l.iter().enumerate() {
+        let diff = digit as i64 - *r.get(i).unwrap_or(&0) as i64 - borrow;
+        out.push(diff.rem_euclid(1 << 32) as u32);
+        borrow = (diff < 0) as i64;
+    }
+    out
+}
+
+/// Divides the magnitude in place by a small number, returning the remainder
+fn div_rem_small(magnitude: &mut [u32], divisor: u32) -> u32 {
+    let mut rem = 0_u64;
+    for digit in magnitude.iter_mut().rev() {
+        let acc = (rem << 32) | *digit as u64;
+        *digit = (acc / divisor as u64) as u32;
+        rem = acc % divisor as u64;
+    }
+    rem as u32
+}
+
+impl std::ops::Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: Self) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::new(
+                self.negative,
+                add_magnitudes(&self.magnitude, &rhs.magnitude),
+            );
+        }
+        // Signs differ, so it's really a subtraction, and the bigger one decides the sign
+        match cmp_magnitudes(&self.magnitude, &rhs.magnitude) {
+            Ordering::Less => BigInt::new(
+                rhs.negative,
+                sub_magnitudes(&rhs.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::new(
+                self.negative,
+                sub_magnitudes(&self.magnitude, &rhs.magnitude),
+            ),
+        }
+    }
+}
+
+impl std::ops::Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: Self) -> BigInt {
+        let mut out = vec![0_u32; self.magnitude.len() + rhs.magnitude.len()];
+        for (i, &l) in self.magnitude.iter().enumerate() {
+            let mut carry = 0_u64;
+            for (j, &r) in rhs.magnitude.iter().enumerate() {
+                let acc = out[i + j] as u64 + l as u64 * r as u64 + carry;
+                out[i + j] = acc as u32;
+                carry = acc >> 32;
+            }
+            out[i + rhs.magnitude.len()] = carry as u32;
+        }
+        BigInt::new(self.negative != rhs.negative, out)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(mut n: u128) -> Self {
+        let mut magnitude = vec![];
+        while n > 0 {
+            magnitude.push(n as u32);
+            n >>= 32;
+        }
+        Self::new(false, magnitude)
+    }
+}
+impl From<i128> for BigInt {
+    fn from(n: i128) -> Self {
+        let BigInt { magnitude, .. } = BigInt::from(n.unsigned_abs());
+        Self::new(n < 0, magnitude)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Peel off 9 decimal digits at a time, since 10^9 is the biggest power of 10 in a u32
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = vec![];
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, 1_000_000_000));
+            while magnitude.last() == Some(&0) {
+                magnitude.pop();
+            }
+        }
+        if self.negative {
+            f.write_str("-")?;
+        }
+        match chunks.split_last() {
+            None => f.write_str("0"),
+            Some((most_significant, rest)) => {
+                write!(f, "{most_significant}")?;
+                for chunk in rest.iter().rev() {
+                    write!(f, "{chunk:09}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Invalid integer {s:?}"));
+        }
+        let ten = BigInt::from(10_u128);
+        let n = digits.bytes().fold(BigInt::from(0_u128), |acc, b| {
+            &(&acc * &ten) + &BigInt::from((b - b'0') as u128)
+        });
+        Ok(Self::new(negative, n.magnitude))
+    }
+}
+
+#[cfg(test)]
+mod answer_tests {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_promotion() {
+        let max = Answer::from(i128::MAX);
+        assert_eq!(
+            max.checked_add(1).unwrap(),
+            Answer::U128(i128::MAX as u128 + 1)
+        );
+        let past_u128 = Answer::from(u128::MAX).checked_add(1).unwrap();
+        assert_eq!(
+            past_u128.to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        // Coming back down again lands in the narrowest variant
+        assert_eq!(
+            past_u128.checked_add(Answer::from(big(
+                "-340282366920938463463374607431768211450"
+            ))),
+            Ok(Answer::I128(6))
+        );
+        assert_eq!(
+            Answer::from(2).checked_pow(200).unwrap().to_string(),
+            "1606938044258990275541962092341162602522202993782792835301376"
+        );
+    }
+
+    #[test]
+    fn test_big_arithmetic() {
+        let a = big("123456789012345678901234567890123456789");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!(
+            (&a * &b).to_string(),
+            "-121932631137021795226185032733744855963362292333223746380111126352690"
+        );
+        assert_eq!(
+            (&a + &b).to_string(),
+            "123456788024691357802469135780246913579"
+        );
+        assert_eq!((&b + &a), (&a + &b));
+        assert_eq!(
+            (&a + &big("-123456789012345678901234567890123456789")).to_string(),
+            "0"
+        );
+        assert!(b < a && big("-5") < big("-4"));
+        assert_eq!(big("-0"), BigInt::from(0_i128));
+    }
+
+    #[test]
+    fn test_text_answers() {
+        let text = Answer::from("ABC");
+        assert_eq!(text.to_string(), "ABC");
+        assert!(matches!(
+            text.checked_add(1),
+            Err(AnswerError::NotANumber(_))
+        ));
+        assert!(Answer::sum([Answer::from(1), text]).is_err());
+        assert_eq!(Answer::sum([1_u32, 2, 3]), Ok(Answer::I128(6)));
+    }
+}
@@ -0,0 +1,56 @@
+//! Runs any day's binary from one place, so that `aoc query 2 'power > 1000'` does the same as
+//! `day2 query 'power > 1000'`.
+//!
+//! Usage: aoc [COMMAND] DAY [ARGS...]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // The day is either first, or straight after the command
+    let (day, forwarded) = match args.as_slice() {
+        [day, rest @ ..] if is_day(day) => (day, rest.to_vec()),
+        [command, day, rest @ ..] if is_day(day) => {
+            let mut forwarded = vec![command.clone()];
+            forwarded.extend_from_slice(rest);
+            (day, forwarded)
+        }
+        _ => usage("Expected a day from 1 to 25"),
+    };
+
+    // Every binary in the workspace gets built into the same directory, but if this day's hasn't
+    // been built yet then cargo can build it first
+    let sibling = std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(format!("day{day}{}", std::env::consts::EXE_SUFFIX)));
+    let mut command = match sibling.filter(|path| path.exists()) {
+        Some(path) => Command::new(path),
+        None => {
+            let manifest: PathBuf = [env!("CARGO_MANIFEST_DIR"), "..", "Cargo.toml"]
+                .iter()
+                .collect();
+            let mut cargo = Command::new("cargo");
+            cargo
+                .args(["run", "--quiet", "--manifest-path"])
+                .arg(manifest)
+                .args(["--package", &format!("day{day}"), "--"]);
+            cargo
+        }
+    };
+    let status = command.args(&forwarded).status().unwrap_or_else(|e| {
+        eprintln!("Couldn't run day {day}: {e}");
+        std::process::exit(2);
+    });
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+fn is_day(arg: &str) -> bool {
+    arg.parse::<u32>().is_ok_and(|day| (1..=25).contains(&day))
+}
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{problem}");
+    eprintln!("Usage: aoc [COMMAND] DAY [ARGS...]");
+    std::process::exit(2);
+}
@@ -0,0 +1,53 @@
+//! Summarises the benchmark history written by `dayN bench`, exiting with a failure status if any
+//! day has regressed.
+//!
+//! Usage: perf-report [--history PATH] [--threshold PERCENT] [--last N]
+
+use common::perf;
+
+fn main() {
+    let mut history = perf::history_path();
+    let mut threshold = 10.0;
+    let mut window = 5;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| usage(&format!("{arg} needs a value")))
+        };
+        match arg.as_str() {
+            "--history" => history = value().into(),
+            "--threshold" => {
+                threshold = value()
+                    .trim_end_matches('%')
+                    .parse()
+                    .unwrap_or_else(|_| usage("--threshold should be a percentage"))
+            }
+            "--last" => {
+                window = value()
+                    .parse()
+                    .unwrap_or_else(|_| usage("--last should be a whole number of runs"))
+            }
+            _ => usage(&format!("Unknown argument {arg:?}")),
+        }
+    }
+
+    let runs = match perf::load(&history) {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("Couldn't read {}: {e}", history.display());
+            std::process::exit(2);
+        }
+    };
+    print!("{}", perf::report(&runs, threshold, window));
+    if !perf::regressions(&runs, threshold, window).is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{problem}");
+    eprintln!("Usage: perf-report [--history PATH] [--threshold PERCENT] [--last N]");
+    std::process::exit(2);
+}
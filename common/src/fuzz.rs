@@ -0,0 +1,397 @@
+//! A small property-testing harness for making sure parsers return errors instead of panicking.
+//!
+//! Inputs are either made up from scratch or made by mangling a known-good example, and any
+//! input which causes a panic gets shrunk down and saved as a fixture, so it's replayed on every
+//! later run.
+//!
+//! The number of cases and the random seed can be changed with `FUZZ_CASES` and `FUZZ_SEED`.
+
+use std::cell::Cell;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+const DEFAULT_CASES: usize = 1000;
+const DEFAULT_SEED: u64 = 0x5eed_2023_0a0c_f00d;
+
+/// A xorshift* generator. Not remotely cryptographic, but deterministic and dependency-free.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift, so nudge it away
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `0..n`, or 0 if `n` is 0
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+const DIGITS: &[char] = &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+const LETTERS: &[char] = &[
+    'a', 'b', 'd', 'e', 'g', 'n', 'o', 'r', 'u', 'A', 'G', 'J', 'K', 'L', 'Q', 'S', 'T', 'F',
+];
+const PUNCTUATION: &[char] = &[
+    '.', '#', '*', ':', ';', ',', '|', '-', '=', '(', ')', '$', '+', '/',
+];
+const WHITESPACE: &[char] = &[' ', ' ', '\n', '\n', '\t', '\r'];
+const UNICODE: &[char] = &['é', '§', '€', '🦀', '\u{0}', '\u{feff}'];
+
+/// Chunks which tend to shake out overflows and other edge cases
+const NASTY_CHUNKS: &[&str] = &[
+    "0",
+    "-1",
+    "99999999999999999999",
+    "4294967296",
+    "-9223372036854775809",
+    // These only just fit, so they get past the parsers to overflow whatever uses them
+    "9223372036854775807",
+    "4294967295",
+    "\n\n",
+    "  ",
+];
+
+fn random_char(rng: &mut Rng) -> char {
+    let class = match rng.below(10) {
+        0..=3 => DIGITS,
+        4..=5 => LETTERS,
+        6..=7 => PUNCTUATION,
+        8 => WHITESPACE,
+        _ => UNICODE,
+    };
+    rng.pick(class)
+}
+
+/// A string made from scratch, out of a mix of characters puzzle inputs tend to contain
+pub fn arbitrary(rng: &mut Rng) -> String {
+    let len = rng.below(48);
+    (0..len).map(|_| random_char(rng)).collect()
+}
+
+/// A copy of `seed` with a few random changes made to it, so it's usually *nearly* valid
+pub fn mutate(rng: &mut Rng, seed: &str) -> String {
+    let mut chars: Vec<char> = seed.chars().collect();
+    for _ in 0..=rng.below(4) {
+        let at = rng.below(chars.len() + 1);
+        match rng.below(10) {
+            0 => {
+                let len = 1 + rng.below(8);
+                chars.drain(at..(at + len).min(chars.len()));
+            }
+            1 => {
+                let extra = arbitrary(rng);
+                chars.splice(at..at, extra.chars());
+            }
+            2 if at < chars.len() => chars[at] = random_char(rng),
+            3 => chars.truncate(at),
+            4 => {
+                // Landing in a number swaps the whole thing out, so that the input still parses
+                // and the chunk gets as far as the solving
+                let chunk = rng.pick(NASTY_CHUNKS);
+                let start = chars[..at]
+                    .iter()
+                    .rposition(|c| !c.is_ascii_digit())
+                    .map_or(0, |i| i + 1);
+                let end = chars[at..]
+                    .iter()
+                    .position(|c| !c.is_ascii_digit())
+                    .map_or(chars.len(), |i| at + i);
+                chars.splice(start..end, chunk.chars());
+            }
+            _ => {
+                let mut lines: Vec<String> = chars
+                    .iter()
+                    .collect::<String>()
+                    .split('\n')
+                    .map(str::to_string)
+                    .collect();
+                let i = rng.below(lines.len());
+                let j = rng.below(lines.len());
+                match rng.below(4) {
+                    0 => {
+                        lines.remove(i);
+                    }
+                    1 => {
+                        let line = lines[i].clone();
+                        lines.insert(j, line);
+                    }
+                    2 => lines.swap(i, j),
+                    _ => lines[i].clear(),
+                }
+                chars = lines.join("\n").chars().collect();
+            }
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Cuts `input` down for as long as `still_fails` keeps agreeing, first a line at a time, then
+/// in ever smaller chunks of characters.
+pub fn shrink(input: &str, still_fails: impl Fn(&str) -> bool) -> String {
+    let mut current = input.to_string();
+    loop {
+        let before = current.len();
+
+        let mut i = 0;
+        while i < current.split('\n').count() {
+            let candidate: Vec<&str> = current
+                .split('\n')
+                .enumerate()
+                .filter_map(|(j, line)| (i != j).then_some(line))
+                .collect();
+            let candidate = candidate.join("\n");
+            // Dropping the only line of "" just gives "" again, so make sure we're getting somewhere
+            if candidate.len() < current.len() && still_fails(&candidate) {
+                current = candidate;
+            } else {
+                i += 1;
+            }
+        }
+
+        let mut chunk = (current.chars().count() / 2).max(1);
+        loop {
+            let mut start = 0;
+            while start < current.chars().count() {
+                let candidate: String = current
+                    .chars()
+                    .enumerate()
+                    .filter_map(|(j, c)| (j < start || j >= start + chunk).then_some(c))
+                    .collect();
+                if still_fails(&candidate) {
+                    current = candidate;
+                } else {
+                    start += chunk;
+                }
+            }
+            if chunk == 1 {
+                break;
+            }
+            chunk /= 2;
+        }
+
+        if current.len() == before {
+            return current;
+        }
+    }
+}
+
+thread_local! {
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+static QUIET_HOOK: Once = Once::new();
+
+/// Runs `f`, returning the panic message if it panicked.
+/// The usual "thread panicked at ..." noise is kept quiet while doing so, without stopping any
+/// other threads from reporting their panics as normal.
+pub fn panic_message(f: impl FnOnce()) -> Option<String> {
+    QUIET_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET.with(Cell::get) {
+                default_hook(info);
+            }
+        }));
+    });
+
+    let was_quiet = QUIET.with(|q| q.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    QUIET.with(|q| q.set(was_quiet));
+
+    let payload = result.err()?;
+    let message = if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    };
+    Some(message)
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Saves a failing input under a name derived from its contents, so the same failure found
+/// twice doesn't pile up duplicate files
+fn save_fixture(fixtures: &Path, input: &str) -> std::io::Result<PathBuf> {
+    // FNV-1a
+    let hash = input.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    fs::create_dir_all(fixtures)?;
+    let path = fixtures.join(format!("{hash:016x}.txt"));
+    fs::write(&path, input)?;
+    Ok(path)
+}
+
+/// Checks that `parse` never panics: first on every fixture saved in the `fixtures` directory,
+/// then on each of the `seeds` as they are, then on a whole bunch of generated inputs, roughly
+/// half of which are mangled copies of the seeds.
+///
+/// The first generated input which panics gets shrunk, saved as a new fixture, and reported by
+/// panicking.
+pub fn check_parser<T, E>(fixtures: &Path, seeds: &[&str], parse: impl Fn(&str) -> Result<T, E>) {
+    check_solver(fixtures, seeds, parse, |_| ());
+}
+
+/// Same as `check_parser`, but every input that parses then goes through `solve` as well, so
+/// that inputs which are valid but strange can't panic further along either
+pub fn check_solver<T, E>(
+    fixtures: &Path,
+    seeds: &[&str],
+    parse: impl Fn(&str) -> Result<T, E>,
+    solve: impl Fn(&T),
+) {
+    let run = |input: &str| {
+        panic_message(|| {
+            if let Ok(parsed) = parse(input) {
+                solve(&parsed);
+            }
+        })
+    };
+
+    let mut replay_failures = vec![];
+    if let Ok(entries) = fs::read_dir(fixtures) {
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+        paths.sort();
+        for path in paths {
+            let input = fs::read_to_string(&path).expect("Fixtures should be valid UTF-8");
+            if let Some(message) = run(&input) {
+                replay_failures.push(format!("{}: {message}", path.display()));
+            }
+        }
+    }
+    assert!(
+        replay_failures.is_empty(),
+        "Panicked on saved fixtures:\n{}",
+        replay_failures.join("\n")
+    );
+    // The seeds go through untouched too, so anything a seed was written to reach gets reached
+    // every time rather than only when the mutations happen to leave it alone
+    for seed in seeds {
+        if let Some(message) = run(seed) {
+            panic!("Panicked on seed {seed:?} with {message:?}");
+        }
+    }
+
+    let mut rng = Rng::new(env_or("FUZZ_SEED", DEFAULT_SEED));
+    for case in 0..env_or("FUZZ_CASES", DEFAULT_CASES) {
+        let input = if seeds.is_empty() || rng.below(2) == 0 {
+            arbitrary(&mut rng)
+        } else {
+            let seed = rng.pick(seeds);
+            mutate(&mut rng, seed)
+        };
+        if run(&input).is_some() {
+            let shrunk = shrink(&input, |s| run(s).is_some());
+            let message = run(&shrunk).unwrap_or_default();
+            let saved = match save_fixture(fixtures, &shrunk) {
+                Ok(path) => format!("saved to {}", path.display()),
+                Err(e) => format!("couldn't be saved: {e}"),
+            };
+            panic!("Panicked on case {case} with {message:?}.\nShrunk input {shrunk:?} {saved}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod fuzz_tests {
+    use super::*;
+
+    fn touchy_parser(s: &str) -> Result<u8, String> {
+        if s.contains('§') {
+            panic!("Oh no");
+        }
+        s.trim().parse().map_err(|_| "Not a number".to_string())
+    }
+
+    #[test]
+    fn test_shrink() {
+        let shrunk = shrink("first line\nabc § def\nlast line", |s| s.contains('§'));
+        assert_eq!(shrunk, "§");
+        let shrunk = shrink("1,22,333,4444", |s| s.matches(',').count() >= 2);
+        assert_eq!(shrunk, ",,");
+        assert_eq!(shrink("\n\n", |_| true), "");
+    }
+
+    #[test]
+    fn test_generators_are_deterministic() {
+        let generate = || {
+            let mut rng = Rng::new(42);
+            (0..50)
+                .map(|_| mutate(&mut rng, "Game 1: 3 blue, 4 red"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generate(), generate());
+    }
+
+    #[test]
+    fn test_check_parser_saves_shrunk_fixture() {
+        let fixtures = std::env::temp_dir().join(format!("fuzz-fixtures-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&fixtures);
+
+        let message = panic_message(|| check_parser(&fixtures, &["12"], touchy_parser));
+        assert!(message.unwrap().contains("Shrunk input \"§\""));
+        let saved: Vec<_> = fs::read_dir(&fixtures).unwrap().collect();
+        assert_eq!(saved.len(), 1);
+
+        // The saved fixture now gets replayed before any new inputs are generated
+        let message = panic_message(|| check_parser(&fixtures, &[], touchy_parser));
+        assert!(message.unwrap().contains("saved fixtures"));
+
+        check_parser(&fixtures, &["12"], |s| s.parse::<u8>());
+        fs::remove_dir_all(&fixtures).unwrap();
+    }
+
+    #[test]
+    fn test_check_solver() {
+        let fixtures = std::env::temp_dir().join(format!("solver-fixtures-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&fixtures);
+
+        // Parsing always works here, but solving can't handle anything long
+        let message = panic_message(|| {
+            check_solver(
+                &fixtures,
+                &["12"],
+                |s| Ok::<_, ()>(s.chars().count()),
+                |&n| assert!(n < 3, "Too long"),
+            )
+        });
+        let message = message.unwrap();
+        assert!(message.contains("Too long"), "{message}");
+        let saved = fs::read_dir(&fixtures).unwrap().next().unwrap().unwrap();
+        assert_eq!(fs::read_to_string(saved.path()).unwrap().chars().count(), 3);
+        fs::remove_dir_all(&fixtures).unwrap();
+
+        // Inputs that don't parse never get as far as solving
+        check_solver(
+            &fixtures,
+            &["12"],
+            |_| Err::<(), ()>(()),
+            |_| panic!("Solved"),
+        );
+        let _ = fs::remove_dir_all(&fixtures);
+    }
+}
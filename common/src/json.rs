@@ -0,0 +1,395 @@
+//! Just enough JSON to save and load things from the days, without pulling in a dependency.
+//!
+//! Objects keep their keys in the order they were written, which keeps output stable and easy to
+//! diff. Numbers are all `f64`s, same as JavaScript, so whole numbers are exact up to 2^53.
+
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// An object from (key, value) pairs, which saves writing `.to_string()` on every key
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Json)>) -> Self {
+        Self::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// The value for the key, if this is an object which has it
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number, as long as it's a whole number which fits
+    pub fn as_u32(&self) -> Option<u32> {
+        let n = self.as_f64()?;
+        (n.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&n)).then_some(n as u32)
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Writes the JSON out with two-space indents and each item on its own line
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, Some(0));
+        out
+    }
+
+    /// `indent` is `None` for compact output, otherwise the current nesting depth
+    fn write(&self, out: &mut String, indent: Option<usize>) {
+        match self {
+            Self::Null => out.push_str("null"),
+            Self::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            // JSON has no way of writing infinities or NaN
+            Self::Number(n) if !n.is_finite() => out.push_str("null"),
+            Self::Number(n) => {
+                let _ = write!(out, "{n}");
+            }
+            Self::String(s) => write_string(out, s),
+            Self::Array(items) => {
+                write_items(out, indent, ('[', ']'), items, |out, item, indent| {
+                    item.write(out, indent)
+                })
+            }
+            Self::Object(fields) => write_items(
+                out,
+                indent,
+                ('{', '}'),
+                fields,
+                |out, (key, value), indent| {
+                    write_string(out, key);
+                    out.push_str(if indent.is_some() { ": " } else { ":" });
+                    value.write(out, indent);
+                },
+            ),
+        }
+    }
+}
+
+fn write_items<T>(
+    out: &mut String,
+    indent: Option<usize>,
+    (open, close): (char, char),
+    items: &[T],
+    write_item: impl Fn(&mut String, &T, Option<usize>),
+) {
+    out.push(open);
+    let inner = indent.map(|depth| depth + 1);
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if let Some(depth) = inner {
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+        }
+        write_item(out, item, inner);
+    }
+    if let (Some(depth), false) = (indent, items.is_empty()) {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    }
+    out.push(close);
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Compact, all on one line
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write(&mut out, None);
+        f.write_str(&out)
+    }
+}
+
+impl FromStr for Json {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s, pos: 0 };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("Trailing characters after the JSON"));
+        }
+        Ok(value)
+    }
+}
+
+/// Deeply nested input shouldn't be able to blow the stack
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> String {
+        format!("{msg} at byte {}", self.pos)
+    }
+
+    fn rest(&self) -> &str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    /// Skips past `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected {token:?}")))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("Nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.rest().chars().next() {
+            Some('{') => {
+                self.pos += 1;
+                let mut fields = vec![];
+                if !self.eat("}") {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(":")?;
+                        fields.push((key, self.value(depth + 1)?));
+                        if self.eat("}") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Json::Object(fields))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Json::Array(items))
+            }
+            Some('"') => self.string().map(Json::String),
+            Some('-' | '0'..='9') => self.number(),
+            _ if self.eat("null") => Ok(Json::Null),
+            _ if self.eat("true") => Ok(Json::Bool(true)),
+            _ if self.eat("false") => Ok(Json::Bool(false)),
+            _ => Err(self.error("Expected a JSON value")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let len = self
+            .rest()
+            .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .unwrap_or(self.rest().len());
+        let n = self.rest()[..len]
+            .parse()
+            .map_err(|_| self.error("Invalid number"))?;
+        self.pos += len;
+        Ok(Json::Number(n))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.rest().starts_with('"') {
+            return Err(self.error("Expected a string"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.rest().chars().next() else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let Some(escape) = self.rest().chars().next() else {
+                        return Err(self.error("Unterminated string"));
+                    };
+                    self.pos += 1;
+                    out.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("Invalid escape")),
+                    });
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// The bit after a `\u`, including the second half of a surrogate pair if there is one
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let hex = |parser: &mut Self| {
+            let digits = parser.rest().get(..4).filter(|d| d.is_ascii());
+            let n = digits.and_then(|d| u32::from_str_radix(d, 16).ok());
+            let n = n.ok_or_else(|| parser.error("Invalid \\u escape"))?;
+            parser.pos += 4;
+            Ok::<u32, String>(n)
+        };
+        let high = hex(self)?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid \\u escape"));
+        }
+        if !self.rest().starts_with("\\u") {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        self.pos += 2;
+        let low = hex(self)?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| self.error("Invalid \\u escape"))
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value = Json::object([
+            ("id", Json::Number(3.0)),
+            (
+                "name",
+                Json::String("tab\there \"quoted\" ü 🎲".to_string()),
+            ),
+            (
+                "items",
+                Json::Array(vec![Json::Null, Json::Bool(true), Json::Number(-1.5e3)]),
+            ),
+            ("empty", Json::Array(vec![])),
+        ]);
+        let compact = value.to_string();
+        assert_eq!(
+            compact,
+            r#"{"id":3,"name":"tab\there \"quoted\" ü 🎲","items":[null,true,-1500],"empty":[]}"#
+        );
+        assert_eq!(compact.parse::<Json>(), Ok(value.clone()));
+        assert_eq!(value.pretty().parse::<Json>(), Ok(value.clone()));
+        assert!(value.pretty().contains("\n  \"items\": [\n    null,"));
+
+        assert_eq!(value.get("id").and_then(Json::as_u32), Some(3));
+        assert_eq!(
+            value.get("items").and_then(Json::as_array).map(<[_]>::len),
+            Some(3)
+        );
+        assert_eq!(value.get("missing"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed: Json = r#" { "a" : [1, 2.5e1, "\u00e9\ud83c\udfb2\n"] } "#.parse().unwrap();
+        assert_eq!(
+            parsed,
+            Json::object([(
+                "a",
+                Json::Array(vec![
+                    Json::Number(1.0),
+                    Json::Number(25.0),
+                    Json::String("é🎲\n".to_string())
+                ])
+            )])
+        );
+        assert_eq!(Json::Number(1.5).as_u32(), None);
+        for bad in [
+            "",
+            "[1,]",
+            "{\"a\" 1}",
+            "\"open",
+            "tru",
+            "[1] 2",
+            "\"\\ud800\"",
+            "-",
+        ] {
+            assert!(bad.parse::<Json>().is_err(), "{bad:?}");
+        }
+        let deep = "[".repeat(1000) + &"]".repeat(1000);
+        assert!(deep.parse::<Json>().is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! Bits and pieces shared between the days, which would otherwise end up copy-pasted around.
+
+pub mod answer;
+pub mod fuzz;
+pub mod json;
+pub mod perf;
+pub mod viewer;
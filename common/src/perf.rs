@@ -0,0 +1,350 @@
+//! Benchmark timings, kept in a plain tab-separated history file so that regressions between
+//! commits can be spotted later with `perf-report`.
+//!
+//! Allocation counts are only recorded if the binary being benchmarked opts in with
+//! `#[global_allocator] static ALLOC: CountingAlloc = CountingAlloc;`, otherwise they read as 0.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::hint::black_box;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Where the history lives if `AOC_PERF_HISTORY` isn't set
+pub const DEFAULT_HISTORY_FILE: &str = "perf-history.tsv";
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, but counting every allocation it makes along the way
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// One benchmarked part, as stored in the history file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub commit: String,
+    pub day: u32,
+    pub part: u32,
+    pub median: Duration,
+    /// Allocations made by a single call
+    pub allocations: u64,
+}
+impl Run {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.commit,
+            self.day,
+            self.part,
+            self.median.as_nanos(),
+            self.allocations
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let run = Self {
+            commit: fields.next()?.to_string(),
+            day: fields.next()?.parse().ok()?,
+            part: fields.next()?.parse().ok()?,
+            median: Duration::from_nanos(fields.next()?.parse().ok()?),
+            allocations: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(run)
+    }
+}
+
+/// Times `f` over a number of iterations, returning the median time and how many allocations
+/// one call made.
+pub fn bench<T>(iterations: usize, mut f: impl FnMut() -> T) -> (Duration, u64) {
+    let mut times = Vec::with_capacity(iterations.max(1));
+    let mut allocations = vec![];
+    for _ in 0..iterations.max(1) {
+        let allocs_before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        black_box(f());
+        times.push(start.elapsed());
+        allocations.push(ALLOCATIONS.load(Ordering::Relaxed) - allocs_before);
+    }
+    times.sort();
+    allocations.sort();
+    (times[times.len() / 2], allocations[allocations.len() / 2])
+}
+
+/// Collects the benchmarks for a day, ready to be printed and added to the history
+pub struct Recorder {
+    day: u32,
+    commit: String,
+    runs: Vec<Run>,
+}
+impl Recorder {
+    pub fn new(day: u32) -> Self {
+        Self {
+            day,
+            commit: current_commit(),
+            runs: vec![],
+        }
+    }
+
+    pub fn bench<T>(&mut self, part: u32, iterations: usize, f: impl FnMut() -> T) {
+        let (median, allocations) = bench(iterations, f);
+        println!(
+            "Day {} part {part}: {} median over {iterations} runs, {allocations} allocations",
+            self.day,
+            format_duration(median)
+        );
+        self.runs.push(Run {
+            commit: self.commit.clone(),
+            day: self.day,
+            part,
+            median,
+            allocations,
+        });
+    }
+
+    /// Appends everything benchmarked so far to the history file
+    pub fn save(self) -> io::Result<()> {
+        let path = history_path();
+        append(&path, &self.runs)?;
+        println!("Recorded {} runs in {}", self.runs.len(), path.display());
+        Ok(())
+    }
+}
+
+/// Checks whether the binary was run as `dayN bench [ITERATIONS]`, returning how many iterations
+/// each part should be timed over if so.
+pub fn bench_iterations() -> Option<usize> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some("bench") {
+        return None;
+    }
+    Some(args.next().map_or(10, |n| {
+        n.parse()
+            .expect("The number of iterations should be a whole number")
+    }))
+}
+
+/// The short hash of the commit we're running on, or "unknown" if git isn't around
+pub fn current_commit() -> String {
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .output()
+        .is_ok_and(|out| !out.stdout.is_empty());
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .map(|hash| if dirty { hash + "-dirty" } else { hash })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn history_path() -> PathBuf {
+    std::env::var_os("AOC_PERF_HISTORY")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_HISTORY_FILE))
+}
+
+pub fn append(path: &Path, runs: &[Run]) -> io::Result<()> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_new {
+        writeln!(file, "# commit\tday\tpart\tmedian_ns\tallocations")?;
+    }
+    for run in runs {
+        writeln!(file, "{}", run.to_line())?;
+    }
+    Ok(())
+}
+
+/// Loads every run from the history, oldest first
+pub fn load(path: &Path) -> io::Result<Vec<Run>> {
+    parse_history(BufReader::new(File::open(path)?))
+}
+
+fn parse_history(reader: impl BufRead) -> io::Result<Vec<Run>> {
+    let mut runs = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let run = Run::from_line(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed history entry on line {}: {line:?}", i + 1),
+            )
+        })?;
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub day: u32,
+    pub part: u32,
+    pub commit: String,
+    pub latest: Duration,
+    /// The fastest of the runs before the latest one
+    pub best: Duration,
+    /// How much slower the latest run was than `best`, as a percentage
+    pub slowdown: f64,
+}
+
+/// Groups runs by (day, part), keeping them in the order they were recorded
+fn by_day_and_part(runs: &[Run]) -> BTreeMap<(u32, u32), Vec<&Run>> {
+    let mut groups: BTreeMap<(u32, u32), Vec<&Run>> = BTreeMap::new();
+    for run in runs {
+        groups.entry((run.day, run.part)).or_default().push(run);
+    }
+    groups
+}
+
+/// Finds every day/part whose latest run is more than `threshold` percent slower than the best
+/// of the `window` runs before it.
+pub fn regressions(runs: &[Run], threshold: f64, window: usize) -> Vec<Regression> {
+    let mut found = vec![];
+    for ((day, part), group) in by_day_and_part(runs) {
+        let Some((latest, earlier)) = group.split_last() else {
+            continue;
+        };
+        let recent = &earlier[earlier.len().saturating_sub(window)..];
+        let Some(best) = recent.iter().map(|run| run.median).min() else {
+            continue;
+        };
+        let slowdown = 100.0 * (latest.median.as_secs_f64() / best.as_secs_f64().max(1e-12) - 1.0);
+        if slowdown > threshold {
+            found.push(Regression {
+                day,
+                part,
+                commit: latest.commit.clone(),
+                latest: latest.median,
+                best,
+                slowdown,
+            });
+        }
+    }
+    found
+}
+
+/// A human-readable summary of the last `window` runs of every day/part, with any regressions
+/// flagged at the bottom.
+pub fn report(runs: &[Run], threshold: f64, window: usize) -> String {
+    let mut out = String::new();
+    for ((day, part), group) in by_day_and_part(runs) {
+        let recent = &group[group.len().saturating_sub(window + 1)..];
+        let trend: Vec<String> = recent
+            .iter()
+            .map(|run| format!("{} ({})", format_duration(run.median), run.commit))
+            .collect();
+        let allocations = group.last().map_or(0, |run| run.allocations);
+        let _ = writeln!(
+            out,
+            "Day {day:>2} part {part}: {}  [{allocations} allocs]",
+            trend.join(" -> ")
+        );
+    }
+
+    let regressions = regressions(runs, threshold, window);
+    if regressions.is_empty() {
+        let _ = writeln!(out, "\nNo regressions over {threshold}%");
+    } else {
+        let _ = writeln!(out, "\nRegressions over {threshold}%:");
+        for r in regressions {
+            let _ = writeln!(
+                out,
+                "  Day {} part {} at {}: {} vs best {} (+{:.1}%)",
+                r.day,
+                r.part,
+                r.commit,
+                format_duration(r.latest),
+                format_duration(r.best),
+                r.slowdown
+            );
+        }
+    }
+    out
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let nanos = d.as_nanos();
+    if nanos < 1_000 {
+        format!("{nanos}ns")
+    } else if nanos < 1_000_000 {
+        format!("{:.2}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.2}s", nanos as f64 / 1e9)
+    }
+}
+
+#[cfg(test)]
+mod perf_tests {
+    use super::*;
+
+    fn run(commit: &str, day: u32, millis: u64) -> Run {
+        Run {
+            commit: commit.to_string(),
+            day,
+            part: 1,
+            median: Duration::from_millis(millis),
+            allocations: 3,
+        }
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let runs = vec![run("abc123", 1, 5), run("def456-dirty", 12, 1500)];
+        let mut file = String::from("# commit\tday\tpart\tmedian_ns\tallocations\n");
+        for run in &runs {
+            file += &run.to_line();
+            file.push('\n');
+        }
+        assert_eq!(parse_history(file.as_bytes()).unwrap(), runs);
+        assert!(parse_history("abc\t1\t1\tlots\t0".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_regressions() {
+        let runs = vec![
+            // Day 1 was quickest a long time ago, but that's outside the window of 2
+            run("a", 1, 5),
+            run("b", 1, 10),
+            run("c", 1, 11),
+            run("d", 1, 11),
+            // Day 2 got much slower on the latest commit
+            run("a", 2, 10),
+            run("b", 2, 9),
+            run("c", 2, 12),
+        ];
+        let found = regressions(&runs, 10.0, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].day, found[0].commit.as_str()), (2, "c"));
+        assert_eq!(found[0].best, Duration::from_millis(9));
+
+        assert_eq!(regressions(&runs, 10.0, 3)[0].day, 1);
+    }
+}
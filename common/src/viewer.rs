@@ -0,0 +1,380 @@
+//! A tiny interactive terminal explorer for grid-shaped puzzle inputs.
+//!
+//! Everything is drawn with plain ANSI escape codes, and the terminal is flipped into raw mode
+//! with `stty`, so there's nothing to install beyond a vaguely modern terminal.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+/// Pairs of the form (row, col)
+pub type Coords = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+impl Colour {
+    fn offset(&self) -> u8 {
+        *self as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<Colour>,
+    pub bg: Option<Colour>,
+    pub bold: bool,
+}
+impl Style {
+    pub const PLAIN: Style = Style {
+        fg: None,
+        bg: None,
+        bold: false,
+    };
+
+    pub fn fg(colour: Colour) -> Self {
+        Self {
+            fg: Some(colour),
+            ..Self::PLAIN
+        }
+    }
+
+    pub fn bg(colour: Colour) -> Self {
+        Self {
+            bg: Some(colour),
+            ..Self::PLAIN
+        }
+    }
+
+    pub fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    /// Writes `text` in this style, then resets back to plain text
+    pub fn paint(&self, out: &mut String, text: &str) {
+        self.write_sgr(out, false);
+        out.push_str(text);
+        out.push_str("\x1b[0m");
+    }
+
+    /// Writes the SGR escape sequence for this style, resetting whatever came before it
+    fn write_sgr(&self, out: &mut String, reverse: bool) {
+        out.push_str("\x1b[0");
+        if self.bold {
+            out.push_str(";1");
+        }
+        if reverse {
+            out.push_str(";7");
+        }
+        if let Some(fg) = self.fg {
+            let _ = write!(out, ";{}", 30 + fg.offset());
+        }
+        if let Some(bg) = self.bg {
+            let _ = write!(out, ";{}", 40 + bg.offset());
+        }
+        out.push('m');
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: char,
+    pub style: Style,
+}
+impl Cell {
+    pub fn new(glyph: char, style: Style) -> Self {
+        Self { glyph, style }
+    }
+}
+
+/// Anything which can be drawn and poked at by the [`Viewer`].
+pub trait Grid {
+    fn n_rows(&self) -> usize;
+    fn n_cols(&self) -> usize;
+    fn cell(&self, pos: Coords) -> Cell;
+    /// A one-line description of whatever is at `pos`
+    fn inspect(&self, pos: Coords) -> String;
+    /// Handle any key the viewer itself doesn't use. Returns a status message if the key did
+    /// something.
+    fn on_key(&mut self, key: char, cursor: Coords) -> Option<String>;
+    /// Help text for the keys handled by `on_key`
+    fn help(&self) -> &'static str;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Char(char),
+}
+
+/// Decodes one key press from the start of some raw terminal input, returning the key alongside
+/// how many bytes it used up.
+pub fn decode_key(bytes: &[u8]) -> Option<(Key, usize)> {
+    match bytes {
+        [] => None,
+        [0x1b, b'[', b'A', ..] => Some((Key::Up, 3)),
+        [0x1b, b'[', b'B', ..] => Some((Key::Down, 3)),
+        [0x1b, b'[', b'C', ..] => Some((Key::Right, 3)),
+        [0x1b, b'[', b'D', ..] => Some((Key::Left, 3)),
+        [b'\r', ..] | [b'\n', ..] => Some((Key::Char('\n'), 1)),
+        [b, ..] if b.is_ascii() => Some((Key::Char(*b as char), 1)),
+        // Anything non-ASCII isn't bound to anything, so just swallow the whole lot
+        _ => Some((Key::Char('\0'), bytes.len())),
+    }
+}
+
+const VIEWER_HELP: &str = "arrows/hjkl: move  HJKL: move x10  q: quit";
+/// Rows at the bottom of the screen reserved for the status bar
+const STATUS_ROWS: usize = 3;
+
+pub struct Viewer<G: Grid> {
+    grid: G,
+    cursor: Coords,
+    /// Top-left corner of the visible part of the grid
+    offset: Coords,
+    /// Size of the terminal, as (rows, cols)
+    screen: Coords,
+    status: String,
+}
+impl<G: Grid> Viewer<G> {
+    pub fn new(grid: G) -> Self {
+        Self {
+            grid,
+            cursor: (0, 0),
+            offset: (0, 0),
+            screen: (24, 80),
+            status: String::new(),
+        }
+    }
+
+    pub fn with_cursor(mut self, cursor: Coords) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    pub fn grid(&self) -> &G {
+        &self.grid
+    }
+
+    pub fn cursor(&self) -> Coords {
+        self.cursor
+    }
+
+    pub fn set_screen_size(&mut self, rows: usize, cols: usize) {
+        self.screen = (rows, cols);
+    }
+
+    /// Reacts to a single key press. Returns `false` once the user has asked to quit.
+    pub fn handle(&mut self, key: Key) -> bool {
+        let (r, c) = self.cursor;
+        let max_r = self.grid.n_rows().saturating_sub(1);
+        let max_c = self.grid.n_cols().saturating_sub(1);
+        self.cursor = match key {
+            Key::Up | Key::Char('k') => (r.saturating_sub(1), c),
+            Key::Down | Key::Char('j') => ((r + 1).min(max_r), c),
+            Key::Left | Key::Char('h') => (r, c.saturating_sub(1)),
+            Key::Right | Key::Char('l') => (r, (c + 1).min(max_c)),
+            Key::Char('K') => (r.saturating_sub(10), c),
+            Key::Char('J') => ((r + 10).min(max_r), c),
+            Key::Char('H') => (r, c.saturating_sub(10)),
+            Key::Char('L') => (r, (c + 10).min(max_c)),
+            Key::Char('q') | Key::Char('\x03') => return false,
+            Key::Char(other) => {
+                if let Some(status) = self.grid.on_key(other, self.cursor) {
+                    self.status = status;
+                }
+                self.cursor
+            }
+        };
+        true
+    }
+
+    /// Nudges the viewport just enough to keep the cursor on screen
+    fn scroll_to_cursor(&mut self) {
+        let view_rows = self.screen.0.saturating_sub(STATUS_ROWS).max(1);
+        let view_cols = self.screen.1.max(1);
+        let (r, c) = self.cursor;
+        if r < self.offset.0 {
+            self.offset.0 = r;
+        } else if r >= self.offset.0 + view_rows {
+            self.offset.0 = r + 1 - view_rows;
+        }
+        if c < self.offset.1 {
+            self.offset.1 = c;
+        } else if c >= self.offset.1 + view_cols {
+            self.offset.1 = c + 1 - view_cols;
+        }
+    }
+
+    /// Draws a full frame into `out`, ready to be written straight to the terminal
+    pub fn render(&mut self, out: &mut String) {
+        self.scroll_to_cursor();
+        let view_rows = self.screen.0.saturating_sub(STATUS_ROWS).max(1);
+        let view_cols = self.screen.1.max(1);
+
+        out.push_str("\x1b[H");
+        for screen_r in 0..view_rows {
+            let r = self.offset.0 + screen_r;
+            let mut last_style = None;
+            for c in self.offset.1..(self.offset.1 + view_cols).min(self.grid.n_cols()) {
+                if r >= self.grid.n_rows() {
+                    break;
+                }
+                let cell = self.grid.cell((r, c));
+                let is_cursor = (r, c) == self.cursor;
+                let style = (cell.style, is_cursor);
+                if last_style != Some(style) {
+                    cell.style.write_sgr(out, is_cursor);
+                    last_style = Some(style);
+                }
+                out.push(cell.glyph);
+            }
+            out.push_str("\x1b[0m\x1b[K\r\n");
+        }
+
+        let (r, c) = self.cursor;
+        let _ = write!(
+            out,
+            "\x1b[0;1m({r}, {c})\x1b[0m {}\x1b[K\r\n{}\x1b[K\r\n\x1b[2m{VIEWER_HELP}  {}\x1b[0m\x1b[K",
+            self.grid.inspect(self.cursor),
+            self.status,
+            self.grid.help(),
+        );
+    }
+
+    /// Takes over the terminal until the user quits
+    pub fn run(mut self) -> io::Result<()> {
+        let _raw = RawMode::enable()?;
+        if let Some((rows, cols)) = terminal_size() {
+            self.set_screen_size(rows, cols);
+        }
+
+        let mut stdin = io::stdin().lock();
+        let mut stdout = io::stdout().lock();
+        let mut frame = String::new();
+        let mut buf = [0; 16];
+        loop {
+            frame.clear();
+            self.render(&mut frame);
+            stdout.write_all(frame.as_bytes())?;
+            stdout.flush()?;
+
+            let n = stdin.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            let mut bytes = &buf[..n];
+            while let Some((key, used)) = decode_key(bytes) {
+                if !self.handle(key) {
+                    return Ok(());
+                }
+                bytes = &bytes[used..];
+            }
+        }
+    }
+}
+
+/// Guard which keeps the terminal in raw mode (on the alternate screen) for as long as it lives
+struct RawMode {
+    saved: String,
+}
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        io::stdout().flush()?;
+        Ok(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("stty {args:?} failed")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn terminal_size() -> Option<Coords> {
+    let size = stty(&["size"]).ok()?;
+    let (rows, cols) = size.trim().split_once(' ')?;
+    Some((rows.parse().ok()?, cols.parse().ok()?))
+}
+
+#[cfg(test)]
+mod viewer_tests {
+    use super::*;
+
+    struct Checkerboard;
+    impl Grid for Checkerboard {
+        fn n_rows(&self) -> usize {
+            40
+        }
+        fn n_cols(&self) -> usize {
+            100
+        }
+        fn cell(&self, (r, c): Coords) -> Cell {
+            let glyph = if (r + c) % 2 == 0 { '#' } else { '.' };
+            Cell::new(glyph, Style::PLAIN)
+        }
+        fn inspect(&self, (r, c): Coords) -> String {
+            format!("{}", self.cell((r, c)).glyph)
+        }
+        fn on_key(&mut self, _: char, _: Coords) -> Option<String> {
+            None
+        }
+        fn help(&self) -> &'static str {
+            ""
+        }
+    }
+
+    #[test]
+    fn test_decode_key() {
+        assert_eq!(decode_key(b"\x1b[Ajk"), Some((Key::Up, 3)));
+        assert_eq!(decode_key(b"jk"), Some((Key::Char('j'), 1)));
+        assert_eq!(decode_key(b"\r"), Some((Key::Char('\n'), 1)));
+        assert_eq!(decode_key(b""), None);
+    }
+
+    #[test]
+    fn test_cursor_stays_on_screen() {
+        let mut viewer = Viewer::new(Checkerboard);
+        viewer.set_screen_size(10, 20);
+        for _ in 0..12 {
+            viewer.handle(Key::Char('J'));
+            viewer.handle(Key::Char('L'));
+        }
+        // Clamped to the bottom-right corner of the grid
+        assert_eq!(viewer.cursor(), (39, 99));
+
+        let mut frame = String::new();
+        viewer.render(&mut frame);
+        assert_eq!(viewer.offset, (39 + 1 - (10 - STATUS_ROWS), 99 + 1 - 20));
+        assert!(frame.contains("(39, 99)\x1b[0m #"));
+        assert!(!viewer.handle(Key::Char('q')));
+    }
+}
@@ -0,0 +1,194 @@
+//! Part 1 straight off the raw bytes of the whole input, without splitting it into lines first or
+//! allocating anything along the way.
+
+use common::answer::Answer;
+use common::fuzz::Rng;
+
+use crate::{is_blank, CalibrationError, SkippedLine};
+
+/// How to look for digits and line breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scan {
+    /// One byte at a time
+    Bytewise,
+    /// Eight bytes at a time, treating each chunk as a u64 ("SIMD within a register")
+    Swar,
+}
+
+const LO: u64 = 0x0101_0101_0101_0101;
+const HI: u64 = 0x8080_8080_8080_8080;
+
+fn load(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Sets the high bit of every byte in `word` which is an ASCII digit, and nothing else.
+///
+/// The top bit of each byte is masked off first, so that adding to the lower 7 bits can't carry
+/// into the next byte, and the bytes which had it set (which are never ASCII) are dropped at the
+/// end.
+fn digit_bytes(word: u64) -> u64 {
+    let low = word & !HI;
+    let at_least_0 = low + LO * (0x80 - b'0' as u64);
+    let past_9 = low + LO * (0x80 - b'9' as u64 - 1);
+    at_least_0 & !past_9 & !word & HI
+}
+
+/// Sets the high bit of every byte in `word` equal to `b`, and nothing else.
+/// Unlike the usual `(v - LO) & !v & HI` trick there are no false positives from borrowing,
+/// which matters when searching backwards.
+fn matching_bytes(word: u64, b: u8) -> u64 {
+    let v = word ^ (LO * b as u64);
+    !(((v & !HI) + !HI) | v | !HI)
+}
+
+/// Position of the first byte matching, checking 8 at a time where possible
+fn position(
+    bytes: &[u8],
+    scan: Scan,
+    byte_mask: impl Fn(u64) -> u64,
+    is_match: impl Fn(u8) -> bool,
+) -> Option<usize> {
+    let mut i = 0;
+    if scan == Scan::Swar {
+        while i + 8 <= bytes.len() {
+            let mask = byte_mask(load(bytes, i));
+            if mask != 0 {
+                return Some(i + mask.trailing_zeros() as usize / 8);
+            }
+            i += 8;
+        }
+    }
+    bytes[i..].iter().position(|&b| is_match(b)).map(|p| i + p)
+}
+
+/// Position of the last byte matching, checking 8 at a time where possible
+fn rposition(
+    bytes: &[u8],
+    scan: Scan,
+    byte_mask: impl Fn(u64) -> u64,
+    is_match: impl Fn(u8) -> bool,
+) -> Option<usize> {
+    let mut j = bytes.len();
+    if scan == Scan::Swar {
+        while j >= 8 {
+            let mask = byte_mask(load(bytes, j - 8));
+            if mask != 0 {
+                return Some(j - 1 - mask.leading_zeros() as usize / 8);
+            }
+            j -= 8;
+        }
+    }
+    bytes[..j].iter().rposition(|&b| is_match(b))
+}
+
+/// Same as `part1`, but working on the raw input all in one go. Each line is only scanned
+/// forwards as far as its first digit and backwards as far as its last.
+pub fn part1_bytes(input: &[u8], scan: Scan) -> Result<Answer, CalibrationError> {
+    let is_digit = |b: u8| b.is_ascii_digit();
+    // Adding up in a u64 is much quicker than going through `Answer` for every line, and it's
+    // only moved over into the answer on the (very unlikely) chance it's about to overflow
+    let mut total = Answer::zero();
+    let mut partial: u64 = 0;
+    let mut rest = input;
+    let mut line_no = 0;
+    while !rest.is_empty() {
+        line_no += 1;
+        let end = position(rest, scan, |w| matching_bytes(w, b'\n'), |b| b == b'\n')
+            .unwrap_or(rest.len());
+        let line = &rest[..end];
+        rest = &rest[(end + 1).min(rest.len())..];
+
+        let Some(first) = position(line, scan, digit_bytes, is_digit) else {
+            let content = String::from_utf8_lossy(line);
+            if is_blank(&content) {
+                continue;
+            }
+            return Err(CalibrationError::NoDigits(SkippedLine {
+                line_no,
+                content: content.trim_end_matches('\r').to_string(),
+            }));
+        };
+        let last = first + rposition(&line[first..], scan, digit_bytes, is_digit).unwrap();
+        let value = ((line[first] - b'0') * 10 + (line[last] - b'0')) as u64;
+        partial = match partial.checked_add(value) {
+            Some(sum) => sum,
+            None => {
+                total = total.checked_add(partial)?;
+                value
+            }
+        };
+    }
+    Ok(total.checked_add(partial)?)
+}
+
+/// A made-up calibration document of roughly `size` bytes, with every line holding at least one
+/// digit
+pub fn generate_input(size: usize, seed: u64) -> String {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+    let mut rng = Rng::new(seed);
+    let mut input = String::with_capacity(size + 64);
+    while input.len() < size {
+        let len = 1 + rng.below(60);
+        let digit_at = rng.below(len);
+        for i in 0..len {
+            let c = if i == digit_at || rng.below(12) == 0 {
+                (b'0' + rng.below(10) as u8) as char
+            } else {
+                rng.pick(LETTERS) as char
+            };
+            input.push(c);
+        }
+        input.push('\n');
+    }
+    input
+}
+
+#[cfg(test)]
+mod fast_tests {
+    use super::*;
+    use crate::part1;
+
+    /// Both scans should always agree with the matcher-based part 1
+    fn check(input: &str) {
+        let lines: Vec<&str> = input.lines().collect();
+        let expected = part1(&lines);
+        assert_eq!(part1_bytes(input.as_bytes(), Scan::Bytewise), expected);
+        assert_eq!(part1_bytes(input.as_bytes(), Scan::Swar), expected);
+    }
+
+    #[test]
+    fn test_matches_part1() {
+        check("1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet");
+        check("1abc2\r\n\r\nlonger than eight bytes 7 and then some more\n\n");
+        check("ä3ßacht\n\u{3000}\nzzzzzzzzzzzzzzzz9zzzzzzzzzzzzzzzzzz\n");
+        check("");
+        for seed in 0..20 {
+            check(&generate_input(5000, seed));
+        }
+    }
+
+    #[test]
+    fn test_no_digits() {
+        let input = "1abc2\n\nno digits in this rather long line\r\n";
+        let error = part1_bytes(input.as_bytes(), Scan::Swar).unwrap_err();
+        assert_eq!(
+            error,
+            CalibrationError::NoDigits(SkippedLine {
+                line_no: 3,
+                content: "no digits in this rather long line".to_string(),
+            })
+        );
+        check(input);
+    }
+
+    #[test]
+    fn test_byte_masks() {
+        let word = u64::from_le_bytes(*b"a0/9:\n\xb0Z");
+        assert_eq!(digit_bytes(word), 0x80 << 8 | 0x80 << 24);
+        assert_eq!(matching_bytes(word, b'\n'), 0x80 << 40);
+        // A newline right after a zero byte is where the borrowing trick trips up
+        let word = u64::from_le_bytes(*b"\n\x0b\x00\x01\x00\x00\x00\x00");
+        assert_eq!(matching_bytes(word, b'\n'), 0x80);
+    }
+}
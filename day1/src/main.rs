@@ -1,74 +1,405 @@
+mod fast;
+mod matcher;
+mod numerals;
+mod spans;
+mod words;
+
+use common::answer::{Answer, AnswerError};
+use common::perf::{self, CountingAlloc, Recorder};
+use fast::Scan;
+use matcher::{MatchMode, Matcher};
+use numerals::Combine;
+use spans::Span;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use words::WordTable;
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
-    let input: Vec<&'static str> = include_str!("../input")
-        .split('\n')
-        .filter(|s| s.chars().any(|c| !c.is_whitespace()))
-        .collect();
-
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
-}
-
-pub fn part1(input: &[&'static str]) -> u32 {
-    let mut sum: u32 = 0;
-    for line in input {
-        // Part 1 only wants us to find the literal characters 1-9, so I use `char.to_digit`,
-        // alongside a `filtermap` to only get out the values which were successfully parsed
-        let buf: Vec<u32> = Vec::from_iter(line.chars().filter_map(|c| c.to_digit(10)));
-
-        if let (Some(first), Some(last)) = (buf.first(), buf.last()) {
-            sum += (first * 10) + last;
-        } else {
-            panic!("[Part 1] Couldn't find any digits within line {line:?}!");
+    if let Some(iterations) = perf::bench_iterations() {
+        bench(iterations);
+        return;
+    }
+    if std::env::args().nth(1).as_deref() == Some("bench-fast") {
+        let megabytes = std::env::args().nth(2).map_or(100, |n| {
+            n.parse()
+                .expect("The input size should be a whole number of megabytes")
+        });
+        bench_fast(megabytes);
+        return;
+    }
+
+    let mut words = WordTable::english();
+    let mut mode = MatchMode::default();
+    let mut extended = None;
+    let mut lenient = false;
+    let mut show_spans = false;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                let code = args.next().expect("--lang needs a language code");
+                words = WordTable::for_language(&code)
+                    .unwrap_or_else(|| panic!("No built-in words for {code:?}, try en/de/fr/es"));
+            }
+            "--words" => {
+                let file = args.next().expect("--words needs a file");
+                words = WordTable::from_file(Path::new(&file)).expect("Couldn't load the words");
+            }
+            "--mode" => {
+                let name = args.next().expect("--mode needs a match mode");
+                mode = name.parse().unwrap_or_else(|e| panic!("{e}"));
+            }
+            "--extended" => {
+                let rule = args.next().expect("--extended needs a combine rule");
+                extended = Some(rule.parse().unwrap_or_else(|e| panic!("{e}")));
+            }
+            "--lenient" => lenient = true,
+            "--spans" => show_spans = true,
+            _ => path = Some(arg),
         }
     }
-    sum
-}
+    let part1_calibration = Calibration::digits().with_lenient(lenient);
+    let part2_calibration = match extended {
+        Some(combine) => Calibration::extended(combine),
+        None => Calibration::with_words(&words).with_mode(mode),
+    }
+    .with_lenient(lenient);
 
-pub fn part2(input: &[&'static str]) -> u32 {
-    let mut sum: u32 = 0;
+    if let Some(path) = path {
+        // Inputs too big to comfortably fit in memory get streamed straight from disk instead
+        let open = || BufReader::new(File::open(&path).expect("Couldn't open the input file"));
+        print_report(1, part1_calibration.sum_reader(open()));
+        print_report(2, part2_calibration.sum_reader(open()));
+        return;
+    }
 
-    for line in input {
-        let mut buf: Vec<u32> = Vec::with_capacity(line.len());
+    let raw = include_str!("../input");
+    let input: Vec<&'static str> = raw.lines().collect();
+    if show_spans {
+        print_spans(1, &input, &part1_calibration);
+        print_spans(2, &input, &part2_calibration);
+    }
+    if lenient {
+        print_report(1, part1_calibration.sum(&input));
+    } else {
+        // Strict part 1 never has anything to report, so it can take the fast path
+        let total = fast::part1_bytes(raw.as_bytes(), Scan::Swar);
+        print_report(
+            1,
+            total.map(|total| CalibrationReport {
+                total,
+                skipped: vec![],
+            }),
+        );
+    }
+    print_report(2, part2_calibration.sum(&input));
+}
 
-        // Part 2 now asks us to also consider the literal words for digits "one" through "nine",
-        // so I made a helper function for trying to parse a digit value out of the start of
-        // a string.
-        for i in 0..line.len() {
-            if let Some(val) = get_number_at_start(&line[i..]) {
-                buf.push(val);
+fn print_report(part: u32, report: Result<CalibrationReport, impl fmt::Display>) {
+    match report {
+        Ok(report) => {
+            println!("Part {part}: {}", report.total);
+            for skipped in &report.skipped {
+                println!("  Skipped {skipped}");
             }
         }
+        Err(e) => println!("Part {part} failed: {e}"),
+    }
+}
+
+fn print_spans(part: u32, input: &[&str], calibration: &Calibration) {
+    for spans in spans::calibration_spans(input, calibration) {
+        let line = input[spans.line_no - 1];
+        println!(
+            "Part {part} line {}: {}",
+            spans.line_no,
+            spans::highlight(line, &spans)
+        );
+    }
+}
+
+fn bench(iterations: usize) {
+    let input: Vec<&str> = include_str!("../input").lines().collect();
+    let mut recorder = Recorder::new(1);
+    recorder.bench(1, iterations, || part1(&input));
+    let words = WordTable::english();
+    recorder.bench(2, iterations, || part2(&input, &words));
+    recorder
+        .save()
+        .expect("Couldn't save the benchmark results");
+}
+
+/// Compares the byte-level part 1 against the matcher on a generated input of the given size
+fn bench_fast(megabytes: usize) {
+    let input = fast::generate_input(megabytes << 20, 1);
+    let lines: Vec<&str> = input.lines().collect();
+    let time = |name: &str, f: &dyn Fn() -> Result<Answer, CalibrationError>| {
+        let (median, allocations) = perf::bench(5, f);
+        let throughput = input.len() as f64 / median.as_secs_f64() / (1 << 20) as f64;
+        println!(
+            "{name:<9} {:>10} median, {throughput:>8.1} MB/s, {allocations} allocations, total {}",
+            perf::format_duration(median),
+            f().expect("The generated input should be valid")
+        );
+    };
+    println!("Part 1 over {megabytes} MB, {} lines", lines.len());
+    time("matcher", &|| part1(&lines));
+    time("bytewise", &|| {
+        fast::part1_bytes(input.as_bytes(), Scan::Bytewise)
+    });
+    time("swar", &|| fast::part1_bytes(input.as_bytes(), Scan::Swar));
+}
+
+// Part 1 only wants us to find the literal characters 0-9, whereas part 2 also wants the words
+// from the word table, so the two parts are the same apart from what the matcher looks for.
+
+pub fn part1(input: &[&str]) -> Result<Answer, CalibrationError> {
+    Ok(Calibration::digits().sum(input)?.total)
+}
 
-        if let (Some(first), Some(last)) = (buf.first(), buf.last()) {
-            sum += (first * 10) + last;
-        } else {
-            panic!("[Part 2] Couldn't find any digits within line {line:?}!");
+pub fn part2(input: &[&str], words: &WordTable) -> Result<Answer, CalibrationError> {
+    Ok(Calibration::with_words(words).sum(input)?.total)
+}
+
+/// Same as `part1`, but reads the input a line at a time rather than needing it all in memory
+pub fn part1_from_reader(reader: impl BufRead) -> io::Result<Answer> {
+    Ok(Calibration::digits().sum_reader(reader)?.total)
+}
+
+/// Same as `part2`, but reads the input a line at a time rather than needing it all in memory
+pub fn part2_from_reader(reader: impl BufRead, words: &WordTable) -> io::Result<Answer> {
+    Ok(Calibration::with_words(words).sum_reader(reader)?.total)
+}
+
+/// A line without any digits in it, which lenient calibrations skip over
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLine {
+    /// Counting from 1, blank lines included
+    pub line_no: usize,
+    pub content: String,
+}
+impl fmt::Display for SkippedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {:?}", self.line_no, self.content)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalibrationReport {
+    pub total: Answer,
+    /// Always empty for strict calibrations, since they stop at the first bad line instead
+    pub skipped: Vec<SkippedLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// A strict calibration came across a line without any digits in it
+    NoDigits(SkippedLine),
+    Answer(AnswerError),
+}
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoDigits(line) => write!(f, "Couldn't find any digits within {line}"),
+            Self::Answer(e) => e.fmt(f),
         }
     }
+}
+impl std::error::Error for CalibrationError {}
+impl From<AnswerError> for CalibrationError {
+    fn from(e: AnswerError) -> Self {
+        Self::Answer(e)
+    }
+}
+impl From<CalibrationError> for io::Error {
+    fn from(e: CalibrationError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
 
-    sum
+/// Everything deciding how calibration values get read out of a document
+pub struct Calibration {
+    reading: Reading,
+    /// Whether lines without any digits get skipped (and reported), rather than being an error
+    lenient: bool,
 }
 
-/// Given some string, try to parse the beginning to see if it begins with either a digit 1-9,
-/// or the literal word "one" through to "nine"
-fn get_number_at_start(s: &str) -> Option<u32> {
-    let words = [
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
-    for (i, word) in words.into_iter().enumerate() {
-        if s.starts_with(word) {
-            return Some(i as u32 + 1);
+enum Reading {
+    /// Single digits, written as digits or as words from a word table
+    Digits { matcher: Matcher, mode: MatchMode },
+    /// Whole English numbers and runs of digits
+    Numbers(Combine),
+}
+
+impl Calibration {
+    /// Only the literal digits count, as in part 1
+    pub fn digits() -> Self {
+        Self {
+            reading: Reading::Digits {
+                matcher: Matcher::digits(),
+                mode: MatchMode::default(),
+            },
+            lenient: false,
         }
     }
-    if let Some(c) = s.chars().next().and_then(|c| c.to_digit(10)) {
-        return Some(c);
+
+    /// The literal digits and the words in the table count, as in part 2
+    pub fn with_words(words: &WordTable) -> Self {
+        Self {
+            reading: Reading::Digits {
+                matcher: Matcher::new(words),
+                mode: MatchMode::default(),
+            },
+            lenient: false,
+        }
     }
-    None
+
+    /// Whole numbers count, whether written out in English ("one hundred and five") or as runs
+    /// of digits ("105"), with `combine` deciding what the first and last numbers add up to.
+    ///
+    /// Numbers are always read leftmost-longest, since "twenty-three" overlapping with "three"
+    /// doesn't mean anything sensible.
+    pub fn extended(combine: Combine) -> Self {
+        Self {
+            reading: Reading::Numbers(combine),
+            lenient: false,
+        }
+    }
+
+    /// Picks how overlapping digit words are handled. Doesn't affect `extended` calibrations.
+    pub fn with_mode(mut self, new_mode: MatchMode) -> Self {
+        if let Reading::Digits { mode, .. } = &mut self.reading {
+            *mode = new_mode;
+        }
+        self
+    }
+
+    /// Lenient calibrations skip over lines without any digits, listing them in the report,
+    /// whereas strict ones give up with an error
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// The calibration value hidden in the line, which is normally its first and last digits
+    /// glued together into a two-digit number. `None` if the line hasn't got any digits.
+    pub fn value(&self, line: &str) -> Result<Option<Answer>, AnswerError> {
+        match &self.reading {
+            Reading::Digits { matcher, mode } => {
+                // In the default overlapping mode, the matcher keeps track of the first and last
+                // digits as it goes, so there's no need to hang onto every digit in between
+                Ok(matcher
+                    .first_and_last_with(line, *mode)
+                    .map(|(first, last)| Answer::from((first.value * 10) + last.value)))
+            }
+            Reading::Numbers(combine) => {
+                let numbers = numerals::numbers(line);
+                match (numbers.first(), numbers.last()) {
+                    (Some(first), Some(last)) => combine.apply(&first.value, &last.value).map(Some),
+                    _ => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Where the first and last matches behind the line's value are, found the same way as in
+    /// `value`. `None` if the line hasn't got any digits.
+    pub fn spans(&self, line: &str) -> Option<(Span, Span)> {
+        let ((first_start, first_len), (last_start, last_len)) = match &self.reading {
+            Reading::Digits { matcher, mode } => {
+                let (first, last) = matcher.first_and_last_with(line, *mode)?;
+                ((first.start, first.len), (last.start, last.len))
+            }
+            Reading::Numbers(_) => {
+                let numbers = numerals::numbers(line);
+                let (first, last) = (numbers.first()?, numbers.last()?);
+                ((first.start, first.len), (last.start, last.len))
+            }
+        };
+        Some((
+            Span::new(line, first_start..first_start + first_len),
+            Span::new(line, last_start..last_start + last_len),
+        ))
+    }
+
+    /// Adds the value of one line onto the report
+    fn add_line(
+        &self,
+        report: &mut CalibrationReport,
+        line_no: usize,
+        line: &str,
+    ) -> Result<(), CalibrationError> {
+        if is_blank(line) {
+            return Ok(());
+        }
+        match self.value(line)? {
+            Some(value) => report.total = report.total.checked_add(value)?,
+            None => {
+                let skipped = SkippedLine {
+                    line_no,
+                    content: line.to_string(),
+                };
+                if !self.lenient {
+                    return Err(CalibrationError::NoDigits(skipped));
+                }
+                report.skipped.push(skipped);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds up the value of every line. Blank lines are always skipped, without being reported.
+    pub fn sum(&self, input: &[&str]) -> Result<CalibrationReport, CalibrationError> {
+        let mut report = CalibrationReport {
+            total: Answer::zero(),
+            skipped: vec![],
+        };
+        for (i, line) in input.iter().enumerate() {
+            self.add_line(&mut report, i + 1, line)?;
+        }
+        Ok(report)
+    }
+
+    /// Same as `sum`, but reads the input a line at a time rather than needing it all in memory
+    pub fn sum_reader(&self, mut reader: impl BufRead) -> io::Result<CalibrationReport> {
+        let mut report = CalibrationReport {
+            total: Answer::zero(),
+            skipped: vec![],
+        };
+        // Reusing the one buffer means memory use stays flat no matter how many lines there are
+        let mut line = String::new();
+        let mut line_no = 0;
+        while reader.read_line(&mut line)? > 0 {
+            line_no += 1;
+            self.add_line(&mut report, line_no, line.trim_end_matches(['\n', '\r']))?;
+            line.clear();
+        }
+        Ok(report)
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    line.chars().all(|c| c.is_whitespace())
 }
 
 #[cfg(test)]
 mod day1_tests {
-    use crate::{part1, part2};
+    use common::answer::Answer;
+
+    use crate::matcher::{Match, MatchMode, Matcher};
+    use crate::numerals::{numbers, Combine};
+    use crate::spans::{calibration_spans, highlight, LineSpans, MatchKind, Span};
+    use crate::words::WordTable;
+    use crate::{
+        part1, part1_from_reader, part2, part2_from_reader, Calibration, CalibrationError,
+        SkippedLine,
+    };
 
     const TEST_INPUT_1: &[&'static str] = &["1abc2", "pqr3stu8vwx", "a1b2c3d4e5f", "treb7uchet"];
 
@@ -86,11 +417,215 @@ mod day1_tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT_1), EXPECTED_PART_1);
+        assert_eq!(part1(TEST_INPUT_1), Ok(Answer::from(EXPECTED_PART_1)));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT_2), EXPECTED_PART_2);
+        assert_eq!(
+            part2(TEST_INPUT_2, &WordTable::english()),
+            Ok(Answer::from(EXPECTED_PART_2))
+        );
+    }
+
+    #[test]
+    fn test_overlapping_words() {
+        let matcher = Matcher::new(&WordTable::english());
+        let value = |line| {
+            let (first, last) = matcher.first_and_last(line).unwrap();
+            first.value * 10 + last.value
+        };
+        assert_eq!(value("eightwo"), 82);
+        assert_eq!(value("twone"), 21);
+        assert_eq!(value("oneight"), 18);
+        assert_eq!(value("sevenine"), 79);
+        assert_eq!(
+            matcher.first_and_last("xtwone3four"),
+            Some((
+                Match {
+                    start: 1,
+                    len: 3,
+                    value: 2
+                },
+                Match {
+                    start: 7,
+                    len: 4,
+                    value: 4
+                }
+            ))
+        );
+        assert_eq!(matcher.first_and_last("no digits here"), None);
+    }
+
+    #[test]
+    fn test_match_modes() {
+        let values = |mode| {
+            let calibration = Calibration::with_words(&WordTable::english()).with_mode(mode);
+            TEST_INPUT_2
+                .iter()
+                .chain(&["oneight", "twone", "eightwo"])
+                .map(|line| calibration.value(line).unwrap().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let answers = |values: [u32; 10]| values.map(Answer::from);
+        assert_eq!(
+            values(MatchMode::Overlapping),
+            answers([29, 83, 13, 24, 42, 14, 76, 18, 21, 82])
+        );
+        // "xtwone3four" reads as "two" "3" "four", since the "one" is already half used up
+        assert_eq!(
+            values(MatchMode::LeftmostNonOverlapping),
+            answers([29, 83, 13, 24, 42, 14, 76, 11, 22, 88])
+        );
+        // Whereas reading from the right, "zoneight234" is "eight" "2" "3" "4", leaving "zon"
+        assert_eq!(
+            values(MatchMode::RightmostFirst),
+            answers([29, 23, 13, 14, 42, 84, 76, 88, 11, 22])
+        );
+    }
+
+    #[test]
+    fn test_compound_numbers() {
+        let values = |line| {
+            numbers(line)
+                .into_iter()
+                .map(|n| n.value.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values("seventeen eightwo"), ["17", "8"]);
+        assert_eq!(values("twenty-three or twenty three"), ["23", "23"]);
+        assert_eq!(values("one hundred and five, 0042"), ["105", "42"]);
+        assert_eq!(values("two thousand and twenty-three"), ["2023"]);
+        assert_eq!(
+            values("nine hundred ninety-nine million, one thousand"),
+            ["999001000"]
+        );
+        // "and" only counts if there's another number after it
+        assert_eq!(values("one hundred and also"), ["100"]);
+
+        let line = "one hundred and five apples, twenty-three pears";
+        let value = |combine| Calibration::extended(combine).value(line).unwrap().unwrap();
+        assert_eq!(value(Combine::Digits), Answer::from(13));
+        assert_eq!(value(Combine::Numbers), Answer::from(10523));
+        assert_eq!(value(Combine::Sum), Answer::from(128));
+
+        // Runs of digits don't have to fit in any particular integer type
+        let digits = "9".repeat(40);
+        let value = Calibration::extended(Combine::Numbers).value(&digits);
+        assert_eq!(value.unwrap().unwrap().to_string(), "9".repeat(80));
+    }
+
+    #[test]
+    fn test_other_languages() {
+        // "fünf" sits right up against a multi-byte character, which used to slice mid-char
+        let german = &["zweiundfünf", "ä3ßacht", "fünfzehn"];
+        assert_eq!(
+            part2(german, &WordTable::german()),
+            Ok(Answer::from(25 + 38 + 55))
+        );
+        let french = &["huitdeux", "neuf6x"];
+        assert_eq!(
+            part2(french, &WordTable::french()),
+            Ok(Answer::from(82 + 96))
+        );
+        let spanish = &["unodos", "sieteochonueve"];
+        assert_eq!(
+            part2(spanish, &WordTable::spanish()),
+            Ok(Answer::from(12 + 79))
+        );
+    }
+
+    #[test]
+    fn test_custom_word_table() {
+        let words = WordTable::parse("# Roman numerals\ni 1\nii 2\niii 3\n\nv 5").unwrap();
+        // The longest word at each position wins, so "iii" is 3 rather than 1, but the last
+        // word of "xii" is still the lone "i" on the end
+        assert_eq!(part2(&["iiixv", "xii"], &words), Ok(Answer::from(35 + 21)));
+        assert!(WordTable::parse("ten 10").is_err());
+        assert!(WordTable::parse("").is_err());
+    }
+
+    #[test]
+    fn test_from_reader() {
+        // Blank lines should be skipped over, same as when loading the whole input up front
+        let input_1 = TEST_INPUT_1.join("\n") + "\n\n";
+        let input_2 = TEST_INPUT_2.join("\r\n");
+        assert_eq!(
+            part1_from_reader(input_1.as_bytes()).unwrap(),
+            Answer::from(EXPECTED_PART_1)
+        );
+        assert_eq!(
+            part2_from_reader(input_2.as_bytes(), &WordTable::english()).unwrap(),
+            Answer::from(EXPECTED_PART_2)
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input = "1abc2\n\nno digits\ntreb7uchet\nnor here";
+        let skipped = |line_no, content: &str| SkippedLine {
+            line_no,
+            content: content.to_string(),
+        };
+
+        let strict = Calibration::digits().sum_reader(input.as_bytes());
+        assert_eq!(strict.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        let lines: Vec<&str> = input.lines().collect();
+        assert_eq!(
+            Calibration::digits().sum(&lines),
+            Err(CalibrationError::NoDigits(skipped(3, "no digits")))
+        );
+
+        let lenient = Calibration::digits().with_lenient(true);
+        let report = lenient.sum_reader(input.as_bytes()).unwrap();
+        assert_eq!(report.total, Answer::from(12 + 77));
+        // Line numbers count the blank line too, so they match up with the original document
+        assert_eq!(
+            report.skipped,
+            [skipped(3, "no digits"), skipped(5, "nor here")]
+        );
+        assert_eq!(lenient.sum(&lines).unwrap(), report);
+    }
+
+    #[test]
+    fn test_spans() {
+        let span = |bytes, kind| Span { bytes, kind };
+        let input = ["", "xtwone3four", "no digits", "oneight", "ä7"];
+        let calibration = Calibration::with_words(&WordTable::english());
+        assert_eq!(
+            calibration_spans(&input, &calibration),
+            [
+                LineSpans {
+                    line_no: 2,
+                    first: span(1..4, MatchKind::Word),
+                    last: span(7..11, MatchKind::Word),
+                },
+                LineSpans {
+                    line_no: 4,
+                    first: span(0..3, MatchKind::Word),
+                    last: span(2..7, MatchKind::Word),
+                },
+                LineSpans {
+                    line_no: 5,
+                    first: span(2..3, MatchKind::Digit),
+                    last: span(2..3, MatchKind::Digit),
+                },
+            ]
+        );
+        let (first, last) = Calibration::digits().spans("xtwone3four").unwrap();
+        assert_eq!((first.bytes, last.bytes), (6..7, 6..7));
+        let (first, last) = Calibration::extended(Combine::Sum)
+            .spans("twenty-one and 305")
+            .unwrap();
+        assert_eq!(first, span(0..10, MatchKind::Word));
+        assert_eq!(last, span(15..18, MatchKind::Digit));
+
+        // The shared "e" of "oneight" gets its own colour
+        let spans = calibration_spans(&input, &calibration);
+        assert_eq!(
+            highlight("oneight", &spans[1]),
+            "\x1b[0;1;32mon\x1b[0m\x1b[0;1;35me\x1b[0m\x1b[0;1;36might\x1b[0m"
+        );
+        assert_eq!(highlight("ä7", &spans[2]), "ä\x1b[0;1;35m7\x1b[0m");
     }
 }
@@ -0,0 +1,208 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+use crate::words::WordTable;
+
+/// Where a digit (or digit word) was found in a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Byte offset of the start of the match
+    pub start: usize,
+    /// Length in bytes
+    pub len: usize,
+    pub value: u32,
+}
+
+/// How to pick out digits when words overlap, like the "one" and "eight" in "oneight"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// Every word counts, even if it shares letters with another, so "oneight" reads as 1 then 8.
+    /// This is what the puzzle itself wants.
+    #[default]
+    Overlapping,
+    /// Reads left to right, taking the longest word at each position then carrying on after it,
+    /// so "oneight" reads as just 1 and "eightwo" as just 8.
+    LeftmostNonOverlapping,
+    /// Reads right to left, taking the longest word ending at each position then carrying on
+    /// before it, so "oneight" reads as just 8 and "eightwo" as just 2.
+    RightmostFirst,
+}
+impl std::str::FromStr for MatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overlapping" => Ok(Self::Overlapping),
+            "leftmost" => Ok(Self::LeftmostNonOverlapping),
+            "rightmost" => Ok(Self::RightmostFirst),
+            _ => Err(format!(
+                "Unknown match mode {s:?}, try overlapping/leftmost/rightmost"
+            )),
+        }
+    }
+}
+
+/// Finds every digit and digit word in a line in a single pass, using an Aho–Corasick automaton
+/// built from the word table.
+///
+/// The automaton works on bytes rather than chars, which is fine since a UTF-8 word can only
+/// ever match starting on a char boundary.
+pub struct Matcher {
+    /// Full transition table, one row per state, so every byte is a single lookup
+    transitions: Vec<[u32; 256]>,
+    /// The (length, value) of every word which ends on reaching each state, longest first
+    outputs: Vec<Vec<(usize, u32)>>,
+}
+
+const ROOT: usize = 0;
+const NONE: u32 = u32::MAX;
+
+impl Matcher {
+    /// A matcher for the literal digits 0-9, as in part 1
+    pub fn digits() -> Self {
+        Self::build(std::iter::empty())
+    }
+
+    /// A matcher for the literal digits, plus every word in the table
+    pub fn new(words: &WordTable) -> Self {
+        Self::build(words.words())
+    }
+
+    fn build<'a>(words: impl Iterator<Item = (&'a str, u32)>) -> Self {
+        let mut matcher = Self {
+            transitions: vec![[NONE; 256]],
+            outputs: vec![vec![]],
+        };
+        let digits = ('0'..='9').zip(0..).map(|(c, n)| (c.to_string(), n));
+        let words = words.map(|(word, value)| (word.to_string(), value));
+        for (word, value) in digits.chain(words) {
+            matcher.insert(&word, value);
+        }
+
+        // Breadth-first, so that every state's failure link is finished before its children
+        // need it. Any transition missing from the trie gets filled in with wherever the
+        // failure link would have gone, leaving a plain DFA behind.
+        let mut fail = vec![ROOT; matcher.transitions.len()];
+        let mut queue = VecDeque::new();
+        for b in 0..256 {
+            match matcher.transitions[ROOT][b] {
+                NONE => matcher.transitions[ROOT][b] = ROOT as u32,
+                child => queue.push_back(child as usize),
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            let fallback = fail[state];
+            let inherited = matcher.outputs[fallback].clone();
+            matcher.outputs[state].extend(inherited);
+            matcher.outputs[state].sort_by_key(|&(len, _)| Reverse(len));
+            for b in 0..256 {
+                match matcher.transitions[state][b] {
+                    NONE => matcher.transitions[state][b] = matcher.transitions[fallback][b],
+                    child => {
+                        fail[child as usize] = matcher.transitions[fallback][b] as usize;
+                        queue.push_back(child as usize);
+                    }
+                }
+            }
+        }
+        matcher
+    }
+
+    fn insert(&mut self, word: &str, value: u32) {
+        if word.is_empty() {
+            return;
+        }
+        let mut state = ROOT;
+        for &b in word.as_bytes() {
+            state = match self.transitions[state][b as usize] {
+                NONE => {
+                    self.transitions.push([NONE; 256]);
+                    self.outputs.push(vec![]);
+                    let child = self.transitions.len() - 1;
+                    self.transitions[state][b as usize] = child as u32;
+                    child
+                }
+                child => child as usize,
+            };
+        }
+        // If the same word turns up twice, the first value it was given sticks
+        if self.outputs[state].is_empty() {
+            self.outputs[state].push((word.len(), value));
+        }
+    }
+
+    /// Every match in the line, overlaps and all, in the order they end
+    pub fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let mut state = ROOT;
+        line.bytes().enumerate().flat_map(move |(i, b)| {
+            state = self.transitions[state][b as usize] as usize;
+            self.outputs[state].iter().map(move |&(len, value)| Match {
+                start: i + 1 - len,
+                len,
+                value,
+            })
+        })
+    }
+
+    /// The first and last matches in the line, where a match starting earlier comes first and
+    /// the longest match wins when several start at the same place.
+    pub fn first_and_last(&self, line: &str) -> Option<(Match, Match)> {
+        let mut first: Option<Match> = None;
+        let mut last: Option<Match> = None;
+        for m in self.matches(line) {
+            if first.is_none_or(|f| (m.start, f.len) < (f.start, m.len)) {
+                first = Some(m);
+            }
+            if last.is_none_or(|l| (m.start, m.len) > (l.start, l.len)) {
+                last = Some(m);
+            }
+        }
+        Some((first?, last?))
+    }
+
+    /// Splits the line into matches following the given mode, in the order they appear
+    pub fn tokens(&self, line: &str, mode: MatchMode) -> Vec<Match> {
+        let mut matches: Vec<Match> = self.matches(line).collect();
+        match mode {
+            MatchMode::Overlapping => {
+                matches.sort_by_key(|m| (m.start, Reverse(m.len)));
+                matches
+            }
+            MatchMode::LeftmostNonOverlapping => {
+                matches.sort_by_key(|m| (m.start, Reverse(m.len)));
+                let mut cursor = 0;
+                matches.retain(|m| {
+                    let keep = m.start >= cursor;
+                    if keep {
+                        cursor = m.start + m.len;
+                    }
+                    keep
+                });
+                matches
+            }
+            MatchMode::RightmostFirst => {
+                matches.sort_by_key(|m| (Reverse(m.start + m.len), Reverse(m.len)));
+                let mut cursor = line.len();
+                matches.retain(|m| {
+                    let keep = m.start + m.len <= cursor;
+                    if keep {
+                        cursor = m.start;
+                    }
+                    keep
+                });
+                matches.reverse();
+                matches
+            }
+        }
+    }
+
+    /// Like `first_and_last`, but following the given mode. Only the overlapping mode gets away
+    /// without holding onto every match in the line.
+    pub fn first_and_last_with(&self, line: &str, mode: MatchMode) -> Option<(Match, Match)> {
+        if mode == MatchMode::Overlapping {
+            return self.first_and_last(line);
+        }
+        let tokens = self.tokens(line, mode);
+        Some((*tokens.first()?, *tokens.last()?))
+    }
+}
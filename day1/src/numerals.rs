@@ -0,0 +1,196 @@
+use common::answer::{Answer, AnswerError, BigInt};
+
+/// A whole number found in a line, either written out in English ("one hundred and five") or as
+/// a run of digits ("105")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    /// Byte offset of the start of the number
+    pub start: usize,
+    /// Length in bytes
+    pub len: usize,
+    pub value: Answer,
+}
+
+/// How the first and last numbers on a line get turned into its calibration value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Combine {
+    /// The first digit of the first number and the last digit of the last number, so
+    /// "twenty-three ... 105" gives 25. The same as the puzzle when every number is one digit.
+    #[default]
+    Digits,
+    /// The first and last numbers written one after the other, so "twenty-three ... 105" gives
+    /// 23105
+    Numbers,
+    /// The first and last numbers added together, so "twenty-three ... 105" gives 128
+    Sum,
+}
+impl std::str::FromStr for Combine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "digits" => Ok(Self::Digits),
+            "numbers" => Ok(Self::Numbers),
+            "sum" => Ok(Self::Sum),
+            _ => Err(format!(
+                "Unknown combine rule {s:?}, try digits/numbers/sum"
+            )),
+        }
+    }
+}
+impl Combine {
+    /// If there's only one number on the line, it's both the first and the last
+    pub fn apply(&self, first: &Answer, last: &Answer) -> Result<Answer, AnswerError> {
+        match self {
+            Self::Digits => {
+                let first = first.to_string();
+                let last = last.to_string();
+                let digits = format!("{}{}", &first[..1], &last[last.len() - 1..]);
+                Ok(Answer::from(digits.parse::<u32>().unwrap()))
+            }
+            Self::Numbers => {
+                let joined: BigInt = format!("{first}{last}").parse().unwrap();
+                Ok(Answer::from(joined))
+            }
+            Self::Sum => first.checked_add(last.clone()),
+        }
+    }
+}
+
+const UNITS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const TEENS: [&str; 10] = [
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [(&str, u64); 3] = [
+    (" billion", 1_000_000_000),
+    (" million", 1_000_000),
+    (" thousand", 1_000),
+];
+
+/// Parsers for each bit of the grammar all return the value they read along with how many
+/// bytes of `s` it took up, or `None` if `s` doesn't start with one.
+type Parsed = Option<(u64, usize)>;
+
+/// Finds whichever word in the list `s` starts with, giving back its index
+fn word_from(s: &str, words: &[&str]) -> Option<(usize, usize)> {
+    words
+        .iter()
+        .position(|word| s.starts_with(word))
+        .map(|i| (i, words[i].len()))
+}
+
+/// "one" to "nine"
+fn unit(s: &str) -> Parsed {
+    word_from(s, &UNITS).map(|(i, len)| (i as u64 + 1, len))
+}
+
+/// "one" to "ninety-nine"
+fn below_hundred(s: &str) -> Parsed {
+    if let Some((i, len)) = word_from(s, &TENS) {
+        let tens = 20 + 10 * i as u64;
+        // Both "twenty-three" and "twenty three" are fair game
+        for sep in ["-", " "] {
+            if let Some((n, n_len)) = s[len..].strip_prefix(sep).and_then(unit) {
+                return Some((tens + n, len + sep.len() + n_len));
+            }
+        }
+        return Some((tens, len));
+    }
+    // Teens have to go before units, or "seventeen" would just be "seven"
+    if let Some((i, len)) = word_from(s, &TEENS) {
+        return Some((10 + i as u64, len));
+    }
+    unit(s)
+}
+
+/// "one" to "nine hundred and ninety-nine"
+fn below_thousand(s: &str) -> Parsed {
+    if let Some((hundreds, len)) = unit(s) {
+        if let Some(rest) = s[len..].strip_prefix(" hundred") {
+            let len = len + " hundred".len();
+            for sep in [" and ", " "] {
+                if let Some((n, n_len)) = rest.strip_prefix(sep).and_then(below_hundred) {
+                    return Some((hundreds * 100 + n, len + sep.len() + n_len));
+                }
+            }
+            return Some((hundreds * 100, len));
+        }
+    }
+    below_hundred(s)
+}
+
+/// A full number like "two million three hundred thousand and twelve"
+fn number(s: &str) -> Parsed {
+    let (mut group, mut pos) = below_thousand(s)?;
+    let mut total = 0;
+    let mut smallest_scale = u64::MAX;
+    loop {
+        let scale = SCALES
+            .iter()
+            .find(|(word, scale)| *scale < smallest_scale && s[pos..].starts_with(word));
+        let Some((word, scale)) = scale else {
+            return Some((total + group, pos));
+        };
+        total += group * scale;
+        pos += word.len();
+        smallest_scale = *scale;
+
+        // Only carry on past the scale word if there's actually another group after it
+        let next = [" and ", ", ", " "].iter().find_map(|sep| {
+            let (n, n_len) = s[pos..].strip_prefix(sep).and_then(below_thousand)?;
+            Some((n, sep.len() + n_len))
+        });
+        match next {
+            Some((n, len)) => {
+                group = n;
+                pos += len;
+            }
+            None => return Some((total, pos)),
+        }
+    }
+}
+
+/// Every number in the line, read left to right, taking the longest number possible each time.
+/// That means "eightwo" is only 8, since the "two" has already lost its "t".
+pub fn numbers(line: &str) -> Vec<Number> {
+    let mut found = vec![];
+    let mut pos = 0;
+    while let Some(c) = line[pos..].chars().next() {
+        let rest = &line[pos..];
+        if c.is_ascii_digit() {
+            let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+            // Digit runs can be as long as they like, so they go through a BigInt
+            let value: BigInt = rest[..len].parse().unwrap();
+            found.push(Number {
+                start: pos,
+                len,
+                value: Answer::from(value),
+            });
+            pos += len;
+        } else if let Some((value, len)) = number(rest) {
+            found.push(Number {
+                start: pos,
+                len,
+                value: Answer::from(value),
+            });
+            pos += len;
+        } else {
+            pos += c.len_utf8();
+        }
+    }
+    found
+}
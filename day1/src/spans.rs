@@ -0,0 +1,115 @@
+//! Shows exactly which bits of each line ended up making its calibration value
+
+use std::ops::Range;
+
+use common::viewer::{Colour, Style};
+
+use crate::{is_blank, Calibration};
+
+/// Whether a match was written as digits or spelled out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Digit,
+    Word,
+}
+impl MatchKind {
+    /// A word from a custom table could happen to be a digit itself, but then there's no
+    /// telling the two apart anyway
+    pub fn of(text: &str) -> Self {
+        if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+            Self::Digit
+        } else {
+            Self::Word
+        }
+    }
+}
+
+/// Where in a line a match was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offsets into the line
+    pub bytes: Range<usize>,
+    pub kind: MatchKind,
+}
+impl Span {
+    pub fn new(line: &str, bytes: Range<usize>) -> Self {
+        let kind = MatchKind::of(&line[bytes.clone()]);
+        Self { bytes, kind }
+    }
+}
+
+/// The first and last matches of a line which has a calibration value. They're the same span
+/// when there's only the one match, and can overlap, like the "one" and "eight" of "oneight".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineSpans {
+    /// Counting from 1, blank lines included
+    pub line_no: usize,
+    pub first: Span,
+    pub last: Span,
+}
+
+/// The spans behind the value of every line. Lines without a value (blank or lacking digits)
+/// are left out.
+pub fn calibration_spans(input: &[&str], calibration: &Calibration) -> Vec<LineSpans> {
+    input
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !is_blank(line))
+        .filter_map(|(i, line)| {
+            let (first, last) = calibration.spans(line)?;
+            Some(LineSpans {
+                line_no: i + 1,
+                first,
+                last,
+            })
+        })
+        .collect()
+}
+
+const FIRST: Style = Style {
+    fg: Some(Colour::Green),
+    bg: None,
+    bold: true,
+};
+const LAST: Style = Style {
+    fg: Some(Colour::Cyan),
+    bg: None,
+    bold: true,
+};
+const BOTH: Style = Style {
+    fg: Some(Colour::Magenta),
+    bg: None,
+    bold: true,
+};
+
+/// The line with the first match coloured green and the last cyan, using ANSI escapes. Any
+/// letters shared by both are magenta.
+pub fn highlight(line: &str, spans: &LineSpans) -> String {
+    let style_at = |i: usize| match (
+        spans.first.bytes.contains(&i),
+        spans.last.bytes.contains(&i),
+    ) {
+        (true, true) => Some(BOTH),
+        (true, false) => Some(FIRST),
+        (false, true) => Some(LAST),
+        (false, false) => None,
+    };
+    // Splits the line into runs of the same style, only ever cutting on char boundaries
+    let mut out = String::new();
+    let mut run_start = 0;
+    let mut run_style = style_at(0);
+    let boundaries = line.char_indices().map(|(i, _)| i).skip(1);
+    for i in boundaries.chain([line.len()]) {
+        let style = if i < line.len() { style_at(i) } else { None };
+        if style == run_style && i < line.len() {
+            continue;
+        }
+        match run_style {
+            Some(style) => style.paint(&mut out, &line[run_start..i]),
+            None => out.push_str(&line[run_start..i]),
+        }
+        run_start = i;
+        run_style = style;
+    }
+    out
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The words which count as digits in part 2, alongside the digit each one stands for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordTable {
+    words: Vec<(String, u32)>,
+}
+
+const ENGLISH: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const GERMAN: [&str; 9] = [
+    "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+];
+const FRENCH: [&str; 9] = [
+    "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+];
+const SPANISH: [&str; 9] = [
+    "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+];
+
+impl WordTable {
+    /// A table of the words for 1 through 9, in that order
+    fn one_to_nine(words: [&str; 9]) -> Self {
+        Self {
+            words: words
+                .into_iter()
+                .zip(1..)
+                .map(|(word, value)| (word.to_string(), value))
+                .collect(),
+        }
+    }
+
+    pub fn english() -> Self {
+        Self::one_to_nine(ENGLISH)
+    }
+
+    pub fn german() -> Self {
+        Self::one_to_nine(GERMAN)
+    }
+
+    pub fn french() -> Self {
+        Self::one_to_nine(FRENCH)
+    }
+
+    pub fn spanish() -> Self {
+        Self::one_to_nine(SPANISH)
+    }
+
+    /// One of the built-in tables, looked up by its two-letter language code
+    pub fn for_language(code: &str) -> Option<Self> {
+        match code {
+            "en" => Some(Self::english()),
+            "de" => Some(Self::german()),
+            "fr" => Some(Self::french()),
+            "es" => Some(Self::spanish()),
+            _ => None,
+        }
+    }
+
+    /// Reads a table made up of lines of the form "word digit". Blank lines and lines starting
+    /// with `#` are ignored, and the same digit can have as many words as it likes.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut words = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (word, value) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| format!("Line {}: expected \"word digit\"", i + 1))?;
+            let value = match value.parse() {
+                Ok(n @ 0..=9) => n,
+                _ => return Err(format!("Line {}: {value:?} isn't a digit", i + 1)),
+            };
+            words.push((word.trim_end().to_string(), value));
+        }
+        if words.is_empty() {
+            return Err("The word table is empty".to_string());
+        }
+        Ok(Self { words })
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.words
+            .iter()
+            .map(|(word, value)| (word.as_str(), *value))
+    }
+}
@@ -0,0 +1,135 @@
+use common::viewer::{Cell, Colour, Coords, Grid, Style, Viewer};
+
+use crate::{LoopWalk, Mask, Pipe, Pipes, PipesMask};
+
+/// Interactive view over the pipes, for stepping around the loop and checking the inside/outside
+/// mask by eye.
+struct PipeExplorer {
+    pipes: Pipes,
+    /// The full mask, with the loop marked and everything else flood-filled
+    mask: PipesMask,
+    show_mask: bool,
+    walk: LoopWalk,
+    /// Every tile visited so far by `walk`, in order
+    trail: Vec<Coords>,
+    on_trail: Vec<Vec<bool>>,
+}
+impl PipeExplorer {
+    fn new(pipes: Pipes) -> Result<Self, String> {
+        let mut doubled_map = pipes.double()?.make_loop_map()?;
+        doubled_map.flood_fill_all();
+        let mask = doubled_map.undouble();
+
+        let walk = LoopWalk::new(&pipes);
+        let start = pipes.start_location();
+        let mut on_trail = vec![vec![false; pipes.col_count()]; pipes.row_count()];
+        on_trail[start.0][start.1] = true;
+        Ok(Self {
+            pipes,
+            mask,
+            show_mask: false,
+            walk,
+            trail: vec![start],
+            on_trail,
+        })
+    }
+
+    fn step(&mut self) -> Option<Coords> {
+        // The whole loop was walked once already to make the mask, so it can't break now
+        let pos = self.walk.step(&self.pipes).ok().flatten()?;
+        self.trail.push(pos);
+        self.on_trail[pos.0][pos.1] = true;
+        Some(pos)
+    }
+}
+
+fn glyph(pipe: Pipe) -> char {
+    match pipe {
+        Pipe::H => '─',
+        Pipe::V => '│',
+        Pipe::NE => '└',
+        Pipe::SE => '┌',
+        Pipe::NW => '┘',
+        Pipe::SW => '┐',
+        Pipe::Start => 'S',
+        Pipe::Ground => '·',
+    }
+}
+
+impl Grid for PipeExplorer {
+    fn n_rows(&self) -> usize {
+        self.pipes.row_count()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.pipes.col_count()
+    }
+
+    fn cell(&self, (r, c): Coords) -> Cell {
+        let pipe = self.pipes.get((r, c)).unwrap_or(Pipe::Ground);
+        let mut style = Style::PLAIN;
+        if self.show_mask {
+            style = match self.mask.get((r, c)) {
+                Some(Mask::Inside) => Style::bg(Colour::Green),
+                Some(Mask::Outside) => Style::fg(Colour::Blue),
+                Some(Mask::Pipe) => Style::fg(Colour::Yellow),
+                Some(Mask::Unknown) | None => Style::PLAIN,
+            };
+        }
+        if self.trail.last() == Some(&(r, c)) {
+            style = Style::bg(Colour::Red).bold();
+        } else if self.on_trail[r][c] {
+            style = Style {
+                fg: Some(Colour::Magenta),
+                ..style
+            }
+            .bold();
+        }
+        Cell::new(glyph(pipe), style)
+    }
+
+    fn inspect(&self, pos: Coords) -> String {
+        let pipe = self.pipes.get(pos).unwrap_or(Pipe::Ground);
+        let mask = self.mask.get(pos).unwrap_or(Mask::Unknown);
+        let step = match self.trail.iter().position(|p| *p == pos) {
+            Some(i) => format!(", loop step {i}"),
+            None => String::new(),
+        };
+        format!("{pipe:?} pipe, mask {mask:?}{step}")
+    }
+
+    fn on_key(&mut self, key: char, _cursor: Coords) -> Option<String> {
+        match key {
+            'n' | ' ' => Some(match self.step() {
+                Some((r, c)) => format!("Stepped to ({r}, {c})"),
+                None => format!("Loop complete after {} steps", self.trail.len() - 1),
+            }),
+            'N' => {
+                let mut steps = 0;
+                while steps < 10 && self.step().is_some() {
+                    steps += 1;
+                }
+                Some(format!("Stepped {steps} tiles"))
+            }
+            'm' => {
+                self.show_mask = !self.show_mask;
+                Some(format!(
+                    "Mask overlay {}",
+                    if self.show_mask { "on" } else { "off" }
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        "n/space: step  N: step x10  m: toggle mask"
+    }
+}
+
+pub fn run(pipes: Pipes) -> std::io::Result<()> {
+    let start = pipes.start_location();
+    let explorer = PipeExplorer::new(pipes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Viewer::new(explorer).with_cursor(start).run()
+}
@@ -1,5 +1,12 @@
+mod explorer;
+
+use common::answer::Answer;
+use common::perf::{self, CountingAlloc, Recorder};
 use std::{collections::BTreeSet, vec};
 
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 type Coords = (usize, usize);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,8 +21,8 @@ enum Pipe {
     Ground,
 }
 impl Pipe {
-    fn from_char(c: char) -> Pipe {
-        match c {
+    fn from_char(c: char) -> Option<Pipe> {
+        Some(match c {
             '-' => Pipe::H,
             '|' => Pipe::V,
             'L' => Pipe::NE,
@@ -24,8 +31,8 @@ impl Pipe {
             'F' => Pipe::SE,
             'S' => Pipe::Start,
             '.' => Pipe::Ground,
-            _ => panic!("Invalid pipe char: {c}"),
-        }
+            _ => return None,
+        })
     }
 
     fn double(&self) -> [[Pipe; 2]; 2] {
@@ -48,19 +55,39 @@ struct Pipes {
     rows: Vec<Vec<Pipe>>,
 }
 impl Pipes {
-    fn from_input(input: &str) -> Self {
+    fn from_input(input: &str) -> Result<Self, String> {
         let rows: Vec<Vec<Pipe>> = input
+            .trim_end_matches(['\n', '\r'])
             .lines()
-            .map(|l| l.chars().map(Pipe::from_char).collect())
-            .collect();
-
-        if !rows.is_empty() {
-            assert!(rows.iter().all(|r| r.len() == rows[0].len()));
-        } else {
-            panic!("Pipe data is empty, something went very very wrong...");
+            .enumerate()
+            .map(|(r, l)| {
+                l.chars()
+                    .enumerate()
+                    .map(|(c, ch)| {
+                        Pipe::from_char(ch).ok_or_else(|| {
+                            format!("Line {}: invalid pipe {ch:?} in column {}", r + 1, c + 1)
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        if rows.is_empty() || rows[0].is_empty() {
+            return Err("Pipe data is empty".to_string());
+        }
+        if let Some(r) = rows.iter().position(|r| r.len() != rows[0].len()) {
+            return Err(format!(
+                "Line {}: every row should be {} pipes long",
+                r + 1,
+                rows[0].len()
+            ));
+        }
+        let starts = rows.iter().flatten().filter(|p| **p == Pipe::Start).count();
+        if starts != 1 {
+            return Err(format!("There should be exactly one start, not {starts}"));
         }
 
-        Self { rows }
+        Ok(Self { rows })
     }
 
     fn start_location(&self) -> (usize, usize) {
@@ -80,75 +107,75 @@ impl Pipes {
         self.rows.get(r).and_then(|row| row.get(c)).copied()
     }
 
-    fn targets(&self, (r, c): Coords) -> [Coords; 2] {
-        let mut pipe = self.rows[r][c];
-        let north = || (r - 1, c);
-        let south = || (r + 1, c);
-        let east = || (r, c + 1);
-        let west = || (r, c - 1);
+    /// The two tiles the pipe at (r, c) leads to, which might be off the bottom or right edge.
+    /// `None` if there's no pipe there, or it leads off the top or left edge.
+    fn targets(&self, (r, c): Coords) -> Option<[Coords; 2]> {
+        let mut pipe = self.get((r, c))?;
+        let north = || Some((r.checked_sub(1)?, c));
+        let south = || Some((r + 1, c));
+        let east = || Some((r, c + 1));
+        let west = || Some((r, c.checked_sub(1)?));
         if pipe == Pipe::Start {
-            pipe = self.start_real_value();
-        }
-        match pipe {
-            Pipe::H => [east(), west()],
-            Pipe::V => [north(), south()],
-            Pipe::NE => [north(), east()],
-            Pipe::SE => [south(), east()],
-            Pipe::NW => [north(), west()],
-            Pipe::SW => [south(), west()],
-            _ => panic!("Targetless pipe section: {pipe:?}"),
+            pipe = self.start_real_value().ok()?;
         }
+        Some(match pipe {
+            Pipe::H => [east()?, west()?],
+            Pipe::V => [north()?, south()?],
+            Pipe::NE => [north()?, east()?],
+            Pipe::SE => [south()?, east()?],
+            Pipe::NW => [north()?, west()?],
+            Pipe::SW => [south()?, west()?],
+            Pipe::Start | Pipe::Ground => return None,
+        })
     }
 
-    fn start_real_value(&self) -> Pipe {
+    fn start_real_value(&self) -> Result<Pipe, String> {
         let (sr, sc) = self.start_location();
         // N S E W
-        let neighbour_coords = [(sr - 1, sc), (sr + 1, sc), (sr, sc + 1), (sr, sc - 1)];
+        let neighbour_coords = [
+            sr.checked_sub(1).map(|r| (r, sc)),
+            Some((sr + 1, sc)),
+            Some((sr, sc + 1)),
+            sc.checked_sub(1).map(|c| (sr, c)),
+        ];
         let mut conns = [false; 4]; // north, south, east, west
-        for ((r, c), conn) in neighbour_coords.into_iter().zip(conns.iter_mut()) {
-            if self.get((r, c)).is_none_or(|p| p == Pipe::Ground) {
+        for (coords, conn) in neighbour_coords.into_iter().zip(conns.iter_mut()) {
+            // The start's own pipe is what's being worked out, so it can't lead back to itself
+            let Some(coords) = coords.filter(|&coords| self.get(coords) != Some(Pipe::Start))
+            else {
                 continue;
-            }
-
-            if self.targets((r, c)).contains(&(sr, sc)) {
+            };
+            if self
+                .targets(coords)
+                .is_some_and(|targets| targets.contains(&(sr, sc)))
+            {
                 *conn = true;
             }
         }
         match conns {
             // Remember, N, S, E, W!
-            [true, true, false, false] => Pipe::H,
-            [false, false, true, true] => Pipe::V,
-            [true, false, true, false] => Pipe::NE,
-            [true, false, false, true] => Pipe::NW,
-            [false, true, true, false] => Pipe::SE,
-            [false, true, false, true] => Pipe::SW,
-            _ => panic!("Invalid connection config: {conns:?}"),
+            [true, true, false, false] => Ok(Pipe::V),
+            [false, false, true, true] => Ok(Pipe::H),
+            [true, false, true, false] => Ok(Pipe::NE),
+            [true, false, false, true] => Ok(Pipe::NW),
+            [false, true, true, false] => Ok(Pipe::SE),
+            [false, true, false, true] => Ok(Pipe::SW),
+            _ => Err(format!(
+                "The start should connect to exactly two pipes, not {}",
+                conns.iter().filter(|&&conn| conn).count()
+            )),
         }
     }
 
-    fn make_loop_map(&self) -> PipesMask {
-        // Make a 2D vec of bools, with the exact same size as
-        let mut map = PipesMask::same_size_as(&self);
-
-        let mut last_pos = self.start_location();
-
-        // make the first move
-        let mut curr_pos = self.targets(last_pos)[0];
-        map.set(curr_pos, Mask::Pipe);
-        let mut curr_pipe = self.get(curr_pos).unwrap();
-        while curr_pipe != Pipe::Start {
-            let targets = self.targets(curr_pos);
-            let next_pos = if targets[0] == last_pos {
-                targets[1]
-            } else {
-                targets[0]
-            };
-            last_pos = curr_pos;
-            curr_pos = next_pos;
-            curr_pipe = self.get(curr_pos).unwrap();
-            map.set(curr_pos, Mask::Pipe);
+    /// Marks every tile of the loop through the start, or fails if there isn't one
+    fn make_loop_map(&self) -> Result<PipesMask, String> {
+        let mut map = PipesMask::same_size_as(self);
+
+        let mut walk = LoopWalk::new(self);
+        while let Some(pos) = walk.step(self)? {
+            map.set(pos, Mask::Pipe);
         }
-        map
+        Ok(map)
     }
 
     fn row_count(&self) -> usize {
@@ -158,7 +185,7 @@ impl Pipes {
         self.rows.get(0).unwrap_or(&Vec::new()).len()
     }
 
-    fn double(&self) -> Self {
+    fn double(&self) -> Result<Self, String> {
         let mut rows = Vec::with_capacity(self.row_count() * 2);
         for r in self.rows.iter() {
             let mut top = Vec::with_capacity(self.col_count() * 2);
@@ -166,7 +193,7 @@ impl Pipes {
 
             for mut p in r.iter().copied() {
                 if p == Pipe::Start {
-                    p = self.start_real_value();
+                    p = self.start_real_value()?;
                     let [[_, tr], b] = p.double();
                     top.extend([Pipe::Start, tr]);
                     bot.extend(b);
@@ -180,7 +207,57 @@ impl Pipes {
             rows.push(bot);
         }
 
-        Self { rows }
+        Ok(Self { rows })
+    }
+}
+
+/// Walks around the loop one tile at a time, starting from the start pipe.
+/// Doesn't borrow the pipes, so that it can be stepped along at leisure by the explorer.
+#[derive(Debug, Clone)]
+struct LoopWalk {
+    last_pos: Coords,
+    curr_pos: Coords,
+    done: bool,
+}
+impl LoopWalk {
+    fn new(pipes: &Pipes) -> Self {
+        let start = pipes.start_location();
+        Self {
+            last_pos: start,
+            curr_pos: start,
+            done: false,
+        }
+    }
+
+    /// Makes one move along the loop, returning where we ended up.
+    /// The final step is the one which lands back on the start.
+    /// Fails if the pipes stop joining up before we get back there.
+    fn step(&mut self, pipes: &Pipes) -> Result<Option<Coords>, String> {
+        if self.done {
+            return Ok(None);
+        }
+        let (r, c) = self.curr_pos;
+        let broken = || format!("The loop is broken at line {}, column {}", r + 1, c + 1);
+        let targets = pipes.targets(self.curr_pos).ok_or_else(broken)?;
+        let next_pos = if targets[0] == self.last_pos {
+            targets[1]
+        } else {
+            targets[0]
+        };
+        // Only pipes leading both ways are joined up, so following them can't wander off
+        // into a cycle which misses the start
+        if !pipes
+            .targets(next_pos)
+            .is_some_and(|back| back.contains(&self.curr_pos))
+        {
+            return Err(broken());
+        }
+        self.last_pos = self.curr_pos;
+        self.curr_pos = next_pos;
+        if pipes.get(next_pos) == Some(Pipe::Start) {
+            self.done = true;
+        }
+        Ok(Some(next_pos))
     }
 }
 
@@ -318,22 +395,111 @@ impl PipesMask {
         total
     }
 
+    /// Shrinks a mask made from `Pipes::double` back down to the size of the original pipes,
+    /// by keeping only the tiles which were at even coordinates.
+    fn undouble(&self) -> Self {
+        let rows = self
+            .rows
+            .iter()
+            .step_by(2)
+            .map(|row| row.iter().step_by(2).copied().collect())
+            .collect();
+        Self { rows }
+    }
+
     fn iter(&self) -> impl Iterator<Item = &Mask> {
         self.rows.iter().flatten()
     }
 }
 
+/// How far along the loop is the furthest point from the start
+fn part1(pipes: &Pipes) -> Result<Answer, String> {
+    Ok(Answer::from(pipes.make_loop_map()?.loop_len() / 2))
+}
+
+/// How many tiles are enclosed by the loop
+fn part2(pipes: &Pipes) -> Result<Answer, String> {
+    // Doubling the pipes opens up gaps between them for the flood fill to squeeze through
+    let mut doubled_map = pipes.double()?.make_loop_map()?;
+    doubled_map.flood_fill_all();
+    Ok(Answer::from(doubled_map.count_insides_on_evens()))
+}
+
 fn main() {
     let input = include_str!("../input");
-    let pipes = Pipes::from_input(input);
-    let map = pipes.make_loop_map();
+    let pipes = Pipes::from_input(input).expect("Couldn't parse the input");
+
+    if let Some(iterations) = perf::bench_iterations() {
+        let mut recorder = Recorder::new(10);
+        recorder.bench(1, iterations, || part1(&pipes));
+        recorder.bench(2, iterations, || part2(&pipes));
+        recorder
+            .save()
+            .expect("Couldn't save the benchmark results");
+        return;
+    }
 
-    let part1 = map.loop_len() / 2;
-    println!("Part 1: {part1}");
+    if std::env::args().nth(1).as_deref() == Some("explore") {
+        explorer::run(pipes).expect("Failed to run the explorer");
+        return;
+    }
 
-    let mut doubled_map = pipes.double().make_loop_map();
-    doubled_map.flood_fill_all();
+    println!("Part 1: {}", part1(&pipes).expect("Couldn't find the loop"));
+    println!("Part 2: {}", part2(&pipes).expect("Couldn't find the loop"));
+}
+
+#[cfg(test)]
+mod day10_tests {
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
 
-    let part2 = doubled_map.count_insides_on_evens();
-    println!("Part 2: {part2}");
+    use crate::{part1, part2, Pipes};
+
+    const EXAMPLE_INPUT: &str = "\
+.....
+.S-7.
+.|.|.
+.L-J.
+.....
+";
+
+    #[test]
+    fn test_loop_len() {
+        let pipes = Pipes::from_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part1(&pipes), Ok(Answer::from(4)));
+        assert_eq!(part2(&pipes), Ok(Answer::from(1)));
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/from_input"
+        ));
+        check_solver(fixtures, &[EXAMPLE_INPUT], Pipes::from_input, |pipes| {
+            let _ = part1(pipes);
+            let _ = part2(pipes);
+        });
+        assert!(Pipes::from_input(".S.\n.S.").is_err());
+
+        // Parsing fine doesn't mean there's a loop to walk round
+        for input in [".S.\n...", "S-7\n|.|\nL-7", "-S-\n...", "|\nS\n|"] {
+            let pipes = Pipes::from_input(input).unwrap();
+            assert!(part1(&pipes).is_err(), "{input:?}");
+            assert!(part2(&pipes).is_err(), "{input:?}");
+        }
+        // Whereas a start in the corner or on a straight is fine
+        let corner = Pipes::from_input("S-7\n|.|\nL-J").unwrap();
+        assert_eq!(
+            (part1(&corner), part2(&corner)),
+            (Ok(Answer::from(4)), Ok(Answer::from(1)))
+        );
+        let straight = Pipes::from_input("F-7\nS.|\nL-J").unwrap();
+        assert_eq!(
+            (part1(&straight), part2(&straight)),
+            (Ok(Answer::from(4)), Ok(Answer::from(1)))
+        );
+    }
 }
@@ -1,35 +1,99 @@
 mod parsing;
+use common::answer::{Answer, AnswerError};
 use parsing::{parse_input, Coords, Galaxy};
 
 fn main() {
     let raw_input = include_str!("../input");
-    println!("Part 1: {}", part1(raw_input));
-    println!("Part 2: {}", part2(raw_input));
+    println!(
+        "Part 1: {}",
+        part1(raw_input).expect("Couldn't parse the input")
+    );
+    println!(
+        "Part 2: {}",
+        part2(raw_input).expect("Couldn't parse the input")
+    );
 }
 
-fn part1(raw_input: &str) -> u64 {
-    let galaxy = parse_input(raw_input, 2);
-    sum_of_distances(&galaxy)
+fn part1(raw_input: &str) -> Result<Answer, String> {
+    let galaxy = parse_input(raw_input, 2)?;
+    sum_of_distances(&galaxy).map_err(|e| e.to_string())
 }
-fn part2(raw_input: &str) -> u64 {
-    let galaxy = parse_input(raw_input, 1_000_000);
-    sum_of_distances(&galaxy)
+fn part2(raw_input: &str) -> Result<Answer, String> {
+    let galaxy = parse_input(raw_input, 1_000_000)?;
+    sum_of_distances(&galaxy).map_err(|e| e.to_string())
 }
 
-fn sum_of_distances(galaxy: &Galaxy) -> u64 {
-    let mut total = 0;
+fn sum_of_distances(galaxy: &Galaxy) -> Result<Answer, AnswerError> {
+    // Each distance fits in a u128 with room to spare, so there'd have to be billions of
+    // galaxies for their total not to
+    let mut total: u128 = 0;
     for (i, g) in galaxy.iter().enumerate() {
         for (j, h) in galaxy.iter().enumerate() {
             if i < j {
                 let dist = taxicab(g, h);
                 // println!("Between galaxy {i} and {j}: {dist}");
-                total += dist;
+                total = total
+                    .checked_add(dist)
+                    .ok_or_else(|| AnswerError::Overflow("the sum of distances".to_string()))?;
             }
         }
     }
-    total
+    Ok(Answer::from(total))
 }
 
-fn taxicab(lhs: &Coords, rhs: &Coords) -> u64 {
-    lhs.x().abs_diff(rhs.x()) + lhs.y().abs_diff(rhs.y())
+fn taxicab(lhs: &Coords, rhs: &Coords) -> u128 {
+    lhs.x().abs_diff(rhs.x()) as u128 + lhs.y().abs_diff(rhs.y()) as u128
+}
+
+#[cfg(test)]
+mod day11_tests {
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
+
+    use crate::{parse_input, part1, part2, sum_of_distances};
+
+    const EXAMPLE_INPUT: &str = "\
+...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+";
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(EXAMPLE_INPUT), Ok(Answer::from(374)));
+    }
+
+    #[test]
+    fn test_expansion() {
+        let galaxy = parse_input(EXAMPLE_INPUT, 100).unwrap();
+        assert_eq!(sum_of_distances(&galaxy), Ok(Answer::from(8410)));
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        // Each part parses the input itself, with its own expansion factor
+        check_solver(
+            fixtures,
+            &[EXAMPLE_INPUT],
+            |s| parse_input(s, 2).map(|_| s.to_string()),
+            |s| {
+                let _ = part1(s);
+                let _ = part2(s);
+            },
+        );
+        assert!(parse_input("#.\n#", 2).is_err());
+    }
 }
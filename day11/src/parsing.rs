@@ -1,5 +1,5 @@
 #[derive(Debug)]
-struct Coords {
+pub struct Coords {
     x: u64,
     y: u64,
 }
@@ -7,24 +7,42 @@ impl Coords {
     fn new(x: u64, y: u64) -> Self {
         Self { x, y }
     }
-    fn x(&self) -> u64 {
+    pub fn x(&self) -> u64 {
         self.x
     }
-    fn y(&self) -> u64 {
+    pub fn y(&self) -> u64 {
         self.y
     }
-    fn taxicab(&self, other: &Self) -> u64 {
-        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
-    }
 }
 
 type UnexpandedGalaxy = Vec<Vec<bool>>;
 /// Just directly translates the given input data into a 2D vector of bools, for later expansion
-fn parse_input_naive(input: &str) -> UnexpandedGalaxy {
-    input
+fn parse_input_naive(input: &str) -> Result<UnexpandedGalaxy, String> {
+    let galaxy: UnexpandedGalaxy = input
+        .trim_end_matches(['\n', '\r'])
         .lines()
-        .map(|line| line.bytes().map(|c| c == b'#').collect())
-        .collect()
+        .enumerate()
+        .map(|(i, line)| {
+            line.chars()
+                .map(|c| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(format!("Line {}: invalid character {c:?}", i + 1)),
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+
+    if let Some(first) = galaxy.first() {
+        if let Some(i) = galaxy.iter().position(|row| row.len() != first.len()) {
+            return Err(format!(
+                "Line {}: every row should be {} characters long",
+                i + 1,
+                first.len()
+            ));
+        }
+    }
+    Ok(galaxy)
 }
 
 pub type Galaxy = Vec<Coords>;
@@ -34,7 +52,7 @@ fn expand_naive_galaxy(galaxy: UnexpandedGalaxy, expansion_factor: usize) -> Gal
         .map(|i| galaxy[i].iter().all(|b| !b))
         .collect();
 
-    let empty_cols: Vec<bool> = (0..galaxy[0].len())
+    let empty_cols: Vec<bool> = (0..galaxy.first().map_or(0, Vec::len))
         .map(|i| galaxy.iter().all(|row| !row[i]))
         .collect();
 
@@ -65,6 +83,12 @@ fn expand_naive_galaxy(galaxy: UnexpandedGalaxy, expansion_factor: usize) -> Gal
     star_coords
 }
 
-pub fn parse_input(input: &str, expansion_factor: usize) -> Galaxy {
-    expand_naive_galaxy(parse_input_naive(input), expansion_factor)
+pub fn parse_input(input: &str, expansion_factor: usize) -> Result<Galaxy, String> {
+    if expansion_factor == 0 {
+        return Err("The expansion factor should be at least 1".to_string());
+    }
+    Ok(expand_naive_galaxy(
+        parse_input_naive(input)?,
+        expansion_factor,
+    ))
 }
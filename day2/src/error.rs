@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// Everything that can be wrong with a line of input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line doesn't start with "Game n:"
+    MissingPrefix,
+    /// The n in "Game n:" isn't a whole number
+    InvalidId(String),
+    /// The same id was already used on an earlier line
+    DuplicateId { id: u32, first_line: usize },
+    /// A draw that isn't of the form "n colour"
+    MalformedDraw(String),
+    /// The n in "n colour" isn't a whole number
+    MalformedCount(String),
+    /// A colour missing from a closed palette, along with the nearest one it does have
+    UnknownColor {
+        name: String,
+        suggestion: Option<String>,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => write!(f, "Each line should start with \"Game n:\""),
+            Self::InvalidId(id) => write!(f, "Invalid game id {id:?}"),
+            Self::DuplicateId { id, first_line } => {
+                write!(f, "Game {id} was already used on line {first_line}")
+            }
+            Self::MalformedDraw(chunk) => {
+                write!(f, "The chunk {chunk:?} should be of the form \"n color\"")
+            }
+            Self::MalformedCount(count) => write!(f, "Invalid cube count {count:?}"),
+            Self::UnknownColor {
+                name,
+                suggestion: Some(suggestion),
+            } => write!(f, "Unknown colour {name:?}, did you mean {suggestion:?}?"),
+            Self::UnknownColor {
+                name,
+                suggestion: None,
+            } => write!(f, "Unknown colour {name:?}"),
+        }
+    }
+}
+
+/// A `ParseError` along with which line it's on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    /// Counting from 1, blank lines included
+    pub line_no: usize,
+    pub error: ParseError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line_no, self.error)
+    }
+}
+
+impl std::error::Error for LineError {}
+
+impl From<LineError> for std::io::Error {
+    fn from(e: LineError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
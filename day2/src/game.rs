@@ -0,0 +1,108 @@
+use crate::palette::{Bag, Color};
+
+/// One handful of cubes pulled out of the bag, in the order they were listed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Round {
+    pub draws: Vec<(Color, u32)>,
+}
+
+impl Round {
+    /// How many cubes were pulled out altogether. Each draw can be anything up to `u32::MAX`, so
+    /// this adds them up in a u64.
+    pub fn total(&self) -> u64 {
+        self.draws.iter().map(|&(_, n)| n as u64).sum()
+    }
+
+    /// How many cubes of the colour were pulled out, adding up repeats
+    pub fn count(&self, color: Color) -> u64 {
+        self.draws
+            .iter()
+            .filter(|(c, _)| *c == color)
+            .map(|&(_, n)| n as u64)
+            .sum()
+    }
+
+    /// Every colour listed more than once, like the red in "1 red, 2 blue, 3 red"
+    pub fn repeated_colors(&self) -> Vec<Color> {
+        let mut repeated = vec![];
+        for (i, (color, _)) in self.draws.iter().enumerate() {
+            let seen_before = self.draws[..i].iter().any(|(c, _)| c == color);
+            if seen_before && !repeated.contains(color) {
+                repeated.push(*color);
+            }
+        }
+        repeated
+    }
+
+    /// The cubes pulled out, with repeats added together
+    pub fn bag(&self) -> Bag {
+        let mut bag = Bag::new();
+        for &(color, n) in &self.draws {
+            bag.set(color, bag.get(color) + n as u64);
+        }
+        bag
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    id: u32,
+    rounds: Vec<Round>,
+}
+
+impl Game {
+    pub fn new(id: u32, rounds: Vec<Round>) -> Self {
+        Self { id, rounds }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn rounds(&self) -> &[Round] {
+        &self.rounds
+    }
+
+    /// Every draw from every round, one after the other
+    pub fn draws(&self) -> impl Iterator<Item = (Color, u32)> + '_ {
+        self.rounds
+            .iter()
+            .flat_map(|round| round.draws.iter().copied())
+    }
+
+    /// How many cubes were pulled out in each round
+    pub fn round_totals(&self) -> Vec<u64> {
+        self.rounds.iter().map(Round::total).collect()
+    }
+
+    /// The index of every round that pulled out more than `total` cubes
+    pub fn rounds_over(&self, total: u64) -> Vec<usize> {
+        (0..self.rounds.len())
+            .filter(|&i| self.rounds[i].total() > total)
+            .collect()
+    }
+
+    /// Each colour listed more than once within the same round, alongside that round's index
+    pub fn repeated_colors(&self) -> Vec<(usize, Color)> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(i, round)| round.repeated_colors().into_iter().map(move |c| (i, c)))
+            .collect()
+    }
+}
+
+/// Every game whose id isn't one more than the game before it (or 1, for the first game), along
+/// with the id it ought to have had. The ids still work fine as they are, but a gap or a jump
+/// backwards often means games were lost or pasted in twice.
+pub fn out_of_sequence(games: &[Game]) -> Vec<(u32, u32)> {
+    let mut expected = 1;
+    let mut found = vec![];
+    for game in games {
+        if game.id != expected {
+            found.push((game.id, expected));
+        }
+        expected = game.id.saturating_add(1);
+    }
+    found
+}
@@ -0,0 +1,149 @@
+//! Working out which bags are most likely to have produced a game.
+//!
+//! Each round is taken to be a handful of cubes pulled out all at once (so without replacement),
+//! with the cubes going back in the bag before the next round. That makes each round a
+//! multivariate hypergeometric draw, and the rounds independent of each other.
+
+use std::cmp::Ordering;
+
+use crate::game::Game;
+use crate::palette::{Bag, Color};
+
+/// ln(n!), exactly (well, as exactly as adding up logs gets) for small n and by Stirling's
+/// series for anything bigger
+fn ln_factorial(n: u64) -> f64 {
+    if n < 256 {
+        return (2..=n).map(|i| (i as f64).ln()).sum();
+    }
+    let n = n as f64;
+    let series = 1.0 / (12.0 * n) - 1.0 / (360.0 * n.powi(3)) + 1.0 / (1260.0 * n.powi(5));
+    n * n.ln() - n + 0.5 * (2.0 * std::f64::consts::PI * n).ln() + series
+}
+
+/// ln(n choose k), which is -inf if there's no way of choosing k things from n
+fn ln_choose(n: u64, k: u64) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+/// The natural log of the chance of the bag producing exactly the rounds of the game. This is
+/// -inf if the game is impossible with the bag.
+pub fn log_likelihood(game: &Game, bag: &Bag) -> f64 {
+    let total: u64 = bag.counts().map(|(_, n)| n).sum();
+    game.rounds()
+        .iter()
+        .map(|round| {
+            let drawn = round.bag();
+            let ways: f64 = drawn
+                .counts()
+                .map(|(color, n)| ln_choose(bag.get(color), n))
+                .sum();
+            ways - ln_choose(total, round.total())
+        })
+        .sum()
+}
+
+/// The chance of the bag producing exactly the rounds of the game
+pub fn probability(game: &Game, bag: &Bag) -> f64 {
+    log_likelihood(game, bag).exp()
+}
+
+/// The bag most likely to have produced a game, and how likely it was to do so
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inference {
+    pub bag: Bag,
+    pub log_likelihood: f64,
+}
+
+/// The biggest budget worth searching. The search below hands the budget out a cube at a time,
+/// and there's no stopping early since the likelihood can keep creeping up all the way to it.
+pub const MAX_BUDGET: u64 = 100_000;
+
+/// The bag with at most `budget` cubes which makes the game most likely, going for the smallest
+/// when there's a tie. `None` if the game needs more than `budget` cubes to be possible at all,
+/// or if the budget's over `MAX_BUDGET`.
+///
+/// Any colour never drawn only makes the game less likely, so the bag is made of drawn colours
+/// alone. For each total number of cubes, the best way of sharing them out maximises a sum of
+/// ln(n choose k) terms, one lot per colour, and those are concave in n. So handing out cubes
+/// one at a time to whichever colour gains the most finds the best bag for every total on the
+/// way up to the budget, and all that's left is picking the best total.
+pub fn most_likely_bag(game: &Game, budget: u64) -> Option<Inference> {
+    if budget > MAX_BUDGET {
+        return None;
+    }
+    let rounds: Vec<Bag> = game.rounds().iter().map(|round| round.bag()).collect();
+    let colors: Vec<Color> = {
+        let mut colors: Vec<Color> = rounds
+            .iter()
+            .flat_map(|bag| bag.counts().map(|(color, _)| color))
+            .collect();
+        colors.sort();
+        colors.dedup();
+        colors
+    };
+    // Every round has to fit in the bag, so that's where to start from
+    let mut bag = Bag::new();
+    for round in &rounds {
+        for (color, n) in round.counts() {
+            bag.set(color, bag.get(color).max(n));
+        }
+    }
+    let mut total: u64 = bag.counts().map(|(_, n)| n).sum();
+    if total > budget {
+        return None;
+    }
+
+    // How much ln(n choose k) goes up by across all the rounds with one more cube of the colour
+    let gain = |color: Color, n: u64| -> f64 {
+        rounds
+            .iter()
+            .map(|round| {
+                let k = round.get(color);
+                ((n + 1) as f64 / (n + 1 - k) as f64).ln()
+            })
+            .sum()
+    };
+    let mut best = Inference {
+        log_likelihood: log_likelihood(game, &bag),
+        bag: bag.clone(),
+    };
+    while total < budget {
+        let Some(&color) = colors.iter().max_by(|&&a, &&b| {
+            gain(a, bag.get(a))
+                .total_cmp(&gain(b, bag.get(b)))
+                .then(b.cmp(&a))
+        }) else {
+            break;
+        };
+        bag.set(color, bag.get(color) + 1);
+        total += 1;
+        let log_likelihood = log_likelihood(game, &bag);
+        if log_likelihood > best.log_likelihood {
+            best = Inference {
+                bag: bag.clone(),
+                log_likelihood,
+            };
+        }
+    }
+    Some(best)
+}
+
+/// Each game's id alongside the chance of the bag producing it, most likely first. Impossible
+/// games end up at the bottom with a chance of 0.
+pub fn rank_by_plausibility(games: &[Game], bag: &Bag) -> Vec<(u32, f64)> {
+    let mut ranked: Vec<(u32, f64)> = games
+        .iter()
+        .map(|game| (game.id(), log_likelihood(game, bag)))
+        .collect();
+    ranked.sort_by(|(id_a, a), (id_b, b)| match b.total_cmp(a) {
+        Ordering::Equal => id_a.cmp(id_b),
+        order => order,
+    });
+    ranked
+        .into_iter()
+        .map(|(id, log_likelihood)| (id, log_likelihood.exp()))
+        .collect()
+}
@@ -1,113 +1,398 @@
+mod error;
+mod game;
+mod infer;
+mod palette;
+mod query;
+mod report;
+mod serial;
+
+use common::answer::{Answer, AnswerError};
+use common::json::Json;
+use common::perf::{self, CountingAlloc, Recorder};
+use error::{LineError, ParseError};
+use game::{Game, Round};
+use palette::{is_color_name, Bag, Palette};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
-    let raw_input = include_str!("../input");
-    let parsed_input = parse_input(raw_input);
-    println!("Part 1: {}", part1(&parsed_input));
-    println!("Part 2: {}", part2(&parsed_input));
-}
+    if let Some(iterations) = perf::bench_iterations() {
+        bench(iterations);
+        return;
+    }
 
-pub fn part1(games: &[Game]) -> u32 {
-    let mut total: u32 = 0;
-    for game in games {
-        if game
-            .draws
-            .iter()
-            .all(|(color, num)| color.max_allowed() >= *num)
-        {
-            total += game.id;
+    // Limits for colours beyond red, green and blue get added to the palette up front, so
+    // they're already there when the input mentions them
+    let mut palette = Palette::standard();
+    let mut limits = Bag::standard_limits();
+    let mut path = None;
+    let mut command = None;
+    let mut json = false;
+    let mut closed = false;
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "rank" => {
+                let budget = args.next_if(|n| n.parse::<u64>().is_ok());
+                command = Some(Command::Rank(budget.map(|n| n.parse().unwrap())));
+            }
+            "report" => command = Some(Command::Report),
+            "export" => command = Some(Command::Export),
+            "query" => {
+                let expr = args.next().expect("query needs an expression");
+                command = Some(Command::Query(expr));
+            }
+            "--json" => json = true,
+            "--closed" => closed = true,
+            "--limit" => {
+                let limit = args.next().expect("--limit needs a colour=count");
+                let (name, n) = limit
+                    .split_once('=')
+                    .and_then(|(name, n)| Some((name, n.parse().ok()?)))
+                    .expect("Limits should look like colour=count");
+                limits.set(palette.intern(name), n);
+            }
+            _ => path = Some(arg),
         }
     }
-    total
-}
 
-pub fn part2(games: &[Game]) -> u32 {
-    let mut total: u32 = 0;
-    for game in games {
-        let mut maxes = [0, 0, 0];
-        for (color, n) in game.draws.iter() {
-            let max = &mut maxes[*color as usize];
-            if *n > *max {
-                *max = *n;
+    // Closing the palette means only the colours it already has (including any with limits)
+    // are allowed in the input
+    if closed {
+        palette = palette.closed();
+    }
+
+    let is_json = path.as_ref().is_some_and(|p| p.ends_with(".json"));
+    let parsed_input = match path {
+        // Inputs too big to comfortably fit in memory get streamed straight from disk instead,
+        // at least when all we want is the answers
+        Some(path) if command.is_none() && !is_json => {
+            let open = || BufReader::new(File::open(&path).expect("Couldn't open the input file"));
+            let part1 = fold_games(open(), palette.clone(), 0, |total, game| {
+                Ok(total + part1_score(game, &limits))
+            });
+            println!("Part 1: {}", part1.unwrap());
+            println!("Part 2: {}", sum_powers(open(), palette).unwrap());
+            return;
+        }
+        Some(path) => {
+            let contents = std::fs::read_to_string(path).expect("Couldn't read the input file");
+            if is_json {
+                let json: Json = contents.parse().expect("Couldn't parse the JSON");
+                serial::from_json(&json, palette).expect("Couldn't load the games")
+            } else {
+                parse_input_with(&contents, palette).expect("Couldn't parse the input")
+            }
+        }
+        None => {
+            parse_input_with(include_str!("../input"), palette).expect("Couldn't parse the input")
+        }
+    };
+    match command {
+        Some(Command::Rank(budget)) => rank(&parsed_input, &limits, budget),
+        Some(Command::Report) => {
+            let report = report::violations(&parsed_input.games, &limits);
+            if json {
+                println!("{}", report.to_json(&parsed_input.palette).pretty());
+            } else {
+                print!("{}", report.to_text(&parsed_input.palette));
+            }
+        }
+        Some(Command::Query(expr)) => {
+            let query = query::parse_query(&expr, &parsed_input.palette)
+                .unwrap_or_else(|e| panic!("Couldn't parse the query: {e}"));
+            let matching = query::select(&parsed_input.games, &query);
+            if json {
+                // Ready to be saved and fed back in later on
+                let matching = Games {
+                    palette: parsed_input.palette.clone(),
+                    games: matching.into_iter().cloned().collect(),
+                };
+                println!("{}", serial::to_json(&matching).pretty());
+                return;
+            }
+            for game in &matching {
+                println!("Game {}", game.id());
+            }
+            println!(
+                "{} of {} games match",
+                matching.len(),
+                parsed_input.games.len()
+            );
+        }
+        Some(Command::Export) if json => {
+            println!("{}", serial::to_json(&parsed_input).pretty())
+        }
+        Some(Command::Export) => print!("{}", serial::render_games(&parsed_input)),
+        None => {
+            for (id, expected) in game::out_of_sequence(&parsed_input.games) {
+                eprintln!("Warning: game {id} is where game {expected} should be");
             }
+            println!("Part 1: {}", part1_with(&parsed_input.games, &limits));
+            let part2 = part2(&parsed_input.games).expect("Couldn't work out part 2");
+            println!("Part 2: {part2}");
         }
-        let product: u32 = maxes.into_iter().product();
-        total += product;
     }
-    total
 }
 
-#[derive(Clone, Copy)]
-pub enum Color {
-    Red,
-    Green,
-    Blue,
+/// Things to do with the input other than solving it
+enum Command {
+    /// Rank the games by how plausible they are, optionally with a budget of cubes
+    Rank(Option<u64>),
+    /// Explain why the impossible games are impossible
+    Report,
+    /// List the games matching a query
+    Query(String),
+    /// Write the games back out, tidied up
+    Export,
+}
+
+/// Lists the games from most to least likely under the limits, each with the bag most likely to
+/// have produced it. The budget defaults to however many cubes the limits add up to.
+fn rank(parsed_input: &Games, limits: &Bag, budget: Option<u64>) {
+    let budget = budget.unwrap_or_else(|| limits.counts().map(|(_, n)| n).sum());
+    if budget > infer::MAX_BUDGET {
+        panic!(
+            "A budget of {budget} cubes would take too long to search, the most is {}",
+            infer::MAX_BUDGET
+        );
+    }
+    for (id, p) in infer::rank_by_plausibility(&parsed_input.games, limits) {
+        let game = parsed_input.games.iter().find(|g| g.id() == id).unwrap();
+        let likeliest = match infer::most_likely_bag(game, budget) {
+            Some(inferred) => format!(
+                "{} (p = {:.3e})",
+                inferred.bag.describe(&parsed_input.palette),
+                infer::probability(game, &inferred.bag)
+            ),
+            None => format!("needs more than {budget} cubes"),
+        };
+        println!("Game {id}: p = {p:.3e}, most likely bag {likeliest}");
+    }
+}
+
+fn bench(iterations: usize) {
+    let parsed_input = parse_input(include_str!("../input")).expect("Couldn't parse the input");
+    let mut recorder = Recorder::new(2);
+    recorder.bench(1, iterations, || part1(&parsed_input.games));
+    recorder.bench(2, iterations, || part2(&parsed_input.games));
+    recorder
+        .save()
+        .expect("Couldn't save the benchmark results");
+}
+
+pub fn part1(games: &[Game]) -> u64 {
+    part1_with(games, &Bag::standard_limits())
+}
+
+/// Same as `part1`, but with any number of cubes of any colours in the bag
+pub fn part1_with(games: &[Game], limits: &Bag) -> u64 {
+    games.iter().map(|game| part1_score(game, limits)).sum()
+}
+
+pub fn part2(games: &[Game]) -> Result<Answer, AnswerError> {
+    let mut total = Answer::zero();
+    for game in games {
+        total = total.checked_add(power(game)?)?;
+    }
+    Ok(total)
+}
+
+/// Same as `part1`, but parses and scores one game at a time straight from the reader
+pub fn part1_from_reader(reader: impl BufRead) -> io::Result<u64> {
+    let limits = Bag::standard_limits();
+    fold_games(reader, Palette::standard(), 0, |total, game| {
+        Ok(total + part1_score(game, &limits))
+    })
+}
+
+/// Same as `part2`, but parses and scores one game at a time straight from the reader
+pub fn part2_from_reader(reader: impl BufRead) -> io::Result<Answer> {
+    sum_powers(reader, Palette::standard())
 }
 
-impl Color {
-    fn max_allowed(&self) -> u32 {
-        match self {
-            Color::Red => 12,
-            Color::Green => 13,
-            Color::Blue => 14,
+fn sum_powers(reader: impl BufRead, palette: Palette) -> io::Result<Answer> {
+    fold_games(reader, palette, Answer::zero(), |total, game| {
+        Ok(total.checked_add(power(game)?)?)
+    })
+}
+
+/// Runs through the games one line at a time, without keeping them all around. Catching a
+/// repeated id would mean remembering every id so far, so games with the same id are each
+/// taken as they come.
+fn fold_games<T>(
+    reader: impl BufRead,
+    palette: Palette,
+    init: T,
+    mut f: impl FnMut(T, &Game) -> io::Result<T>,
+) -> io::Result<T> {
+    let mut games = GameReader::new(palette);
+    let mut total = init;
+    for (i, line) in reader.lines().enumerate() {
+        if let Some(game) = games.read_line(i + 1, &line?)? {
+            total = f(total, &game)?;
         }
     }
+    Ok(total)
 }
 
-impl std::str::FromStr for Color {
-    type Err = ();
+/// The game's id if it could have been played with the given bag, else 0. Ids can go right up
+/// to u32::MAX, so this is widened to keep the total of them from overflowing.
+fn part1_score(game: &Game, limits: &Bag) -> u64 {
+    if game
+        .draws()
+        .all(|(color, num)| limits.get(color) >= num as u64)
+    {
+        game.id() as u64
+    } else {
+        0
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "red" => Ok(Self::Red),
-            "green" => Ok(Self::Green),
-            "blue" => Ok(Self::Blue),
-            _ => Err(()),
+/// The fewest cubes of each colour that the game could have been played with
+pub fn minimum_bag(game: &Game) -> Bag {
+    let mut bag = Bag::new();
+    for (color, n) in game.draws() {
+        if n as u64 > bag.get(color) {
+            bag.set(color, n as u64);
         }
     }
+    bag
 }
 
-pub struct Game {
-    id: u32,
-    draws: Vec<(Color, u32)>,
+/// The power of the smallest bag the game could have been played with
+fn power(game: &Game) -> Result<Answer, AnswerError> {
+    minimum_bag(game).power()
 }
 
-pub fn parse_input(input: &str) -> Vec<Game> {
+/// Every game in an input, along with the palette their colours belong to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Games {
+    pub palette: Palette,
+    pub games: Vec<Game>,
+}
+
+pub fn parse_input(input: &str) -> Result<Games, LineError> {
+    parse_input_with(input, Palette::standard())
+}
+
+/// Same as `parse_input`, but adding any new colours onto an existing palette
+pub fn parse_input_with(input: &str, palette: Palette) -> Result<Games, LineError> {
+    let mut reader = GameReader::new(palette).rejecting_duplicates();
     let mut games = vec![];
-    for line in input.lines() {
-        let (game_id_str, bag_pulls_str) = line
-            .strip_prefix("Game ")
-            .and_then(|s| s.split_once(':'))
-            .expect("Each line should contain a colon character after the line number");
+    for (i, line) in input.lines().enumerate() {
+        games.extend(reader.read_line(i + 1, line)?);
+    }
+    Ok(Games {
+        palette: reader.palette,
+        games,
+    })
+}
 
-        let game_id: u32 = game_id_str.parse().unwrap();
+/// Parses games one line at a time, keeping track of the colours seen so far
+struct GameReader {
+    palette: Palette,
+    /// The line each game id was first seen on, when repeated ids are being rejected. This grows
+    /// with every game, so it's left off when streaming.
+    seen: Option<HashMap<u32, usize>>,
+}
 
-        let mut game_draws = vec![];
+impl GameReader {
+    fn new(palette: Palette) -> Self {
+        Self {
+            palette,
+            seen: None,
+        }
+    }
 
-        for color_chunk in bag_pulls_str.split(|c| c == ',' || c == ';').map(str::trim) {
-            let (n, color): (u32, Color) = color_chunk
-                .split_once(' ')
-                .and_then(|(n, c)| {
-                    if let (Ok(n), Ok(c)) = (n.parse(), c.parse()) {
-                        Some((n, c))
-                    } else {
-                        None
-                    }
-                })
-                .expect("The extracted chunks from the text should be of the form \"n color\"");
+    /// Rejects any game whose id was already used on an earlier line
+    fn rejecting_duplicates(mut self) -> Self {
+        self.seen = Some(HashMap::new());
+        self
+    }
 
-            game_draws.push((color, n));
+    /// The game on the line, or `None` if the line's blank
+    fn read_line(&mut self, line_no: usize, line: &str) -> Result<Option<Game>, LineError> {
+        if line.trim().is_empty() {
+            return Ok(None);
         }
+        let at_line = |error| LineError { line_no, error };
+        let game = parse_line(line, &mut self.palette).map_err(at_line)?;
+        if let Some(seen) = &mut self.seen {
+            if let Some(&first_line) = seen.get(&game.id()) {
+                return Err(at_line(ParseError::DuplicateId {
+                    id: game.id(),
+                    first_line,
+                }));
+            }
+            seen.insert(game.id(), line_no);
+        }
+        Ok(Some(game))
+    }
+}
 
-        games.push(Game {
-            id: game_id,
-            draws: game_draws,
-        });
+fn parse_line(line: &str, palette: &mut Palette) -> Result<Game, ParseError> {
+    let (game_id_str, bag_pulls_str) = line
+        .strip_prefix("Game ")
+        .and_then(|s| s.split_once(':'))
+        .ok_or(ParseError::MissingPrefix)?;
+
+    let game_id: u32 = game_id_str
+        .parse()
+        .map_err(|_| ParseError::InvalidId(game_id_str.to_string()))?;
+
+    let mut rounds = vec![];
+    for round_str in bag_pulls_str.split(';') {
+        let mut round = Round::default();
+        for color_chunk in round_str.split(',').map(str::trim) {
+            let (n, name) = color_chunk
+                .split_once(' ')
+                .filter(|(_, name)| is_color_name(name))
+                .ok_or_else(|| ParseError::MalformedDraw(color_chunk.to_string()))?;
+            let n: u32 = n
+                .parse()
+                .map_err(|_| ParseError::MalformedCount(n.to_string()))?;
+            let color = palette
+                .try_intern(name)
+                .ok_or_else(|| ParseError::UnknownColor {
+                    name: name.to_string(),
+                    suggestion: palette.closest(name).map(str::to_string),
+                })?;
+            round.draws.push((color, n));
+        }
+        rounds.push(round);
     }
-    games
+
+    Ok(Game::new(game_id, rounds))
 }
 
 #[cfg(test)]
 mod day2_tests {
-    use crate::{parse_input, part1, part2};
+    use std::path::Path;
+
+    use common::fuzz::check_solver;
+
+    use crate::error::{LineError, ParseError};
+    use crate::game::out_of_sequence;
+    use crate::infer::{
+        log_likelihood, most_likely_bag, probability, rank_by_plausibility, MAX_BUDGET,
+    };
+    use crate::palette::{Bag, Color, Palette};
+    use crate::query::{parse_query, select};
+    use crate::report::{violations, Violation};
+    use crate::serial::{from_json, render_game, render_games, to_json};
+    use crate::Games;
+    use crate::{
+        minimum_bag, parse_input, parse_input_with, part1, part1_from_reader, part1_with, part2,
+        part2_from_reader,
+    };
+    use common::answer::{Answer, BigInt};
+    use common::fuzz::Rng;
+    use common::json::Json;
 
     const EXAMPLE_INPUT: &'static str = "\
 Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
@@ -116,19 +401,527 @@ Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-    const EXPECTED_PART_1: u32 = 8;
+    const EXPECTED_PART_1: u64 = 8;
 
     const EXPECTED_PART_2: u32 = 2286;
 
     #[test]
     fn test_part1() {
-        let parsed_input = parse_input(EXAMPLE_INPUT);
-        assert_eq!(part1(&parsed_input), EXPECTED_PART_1);
+        let parsed_input = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part1(&parsed_input.games), EXPECTED_PART_1);
     }
 
     #[test]
     fn test_part2() {
-        let parsed_input = parse_input(EXAMPLE_INPUT);
-        assert_eq!(part2(&parsed_input), EXPECTED_PART_2);
+        let parsed_input = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part2(&parsed_input.games), Ok(EXPECTED_PART_2.into()));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let reader = || EXAMPLE_INPUT.as_bytes();
+        assert_eq!(part1_from_reader(reader()).unwrap(), EXPECTED_PART_1);
+        assert_eq!(
+            part2_from_reader(reader()).unwrap(),
+            Answer::from(EXPECTED_PART_2)
+        );
+    }
+
+    #[test]
+    fn test_other_colours() {
+        let input = "\
+Game 1: 3 yellow, 4 red; 1 cyan, 2 green, 6 blue
+Game 2: 2 red, 1 green; 5 blue
+Game 3: 9 yellow; 1 red, 1 green, 1 blue";
+        let mut palette = Palette::standard();
+        let yellow = palette.intern("yellow");
+        let parsed = parse_input_with(input, palette).unwrap();
+        assert_eq!(parsed.palette.len(), 5);
+        assert_eq!(parsed.palette.get("cyan").map(Color::index), Some(4));
+        assert_eq!(parsed.palette.name(yellow), "yellow");
+
+        // Game 1 needs a cyan cube, but there aren't any
+        let limits = Bag::standard_limits().with(yellow, 9);
+        assert_eq!(part1_with(&parsed.games, &limits), 2 + 3);
+        assert_eq!(part1(&parsed.games), 2);
+
+        let bag = minimum_bag(&parsed.games[0]);
+        assert_eq!(bag.get(yellow), 3);
+        assert_eq!(bag.get(Color::GREEN), 2);
+        // Other colours only count towards the power of the games which use them
+        assert_eq!(part2(&parsed.games), Ok(Answer::from(144 + 10 + 9)));
+    }
+
+    #[test]
+    fn test_big_powers() {
+        // Eight colours of a million cubes each is a power of 10^48, too big even for a u128
+        let colours = [
+            "red", "green", "blue", "cyan", "pink", "grey", "teal", "gold",
+        ];
+        let round: Vec<String> = colours.iter().map(|c| format!("1000000 {c}")).collect();
+        let input = format!(
+            "Game 1: {}\nGame 2: {}\n",
+            round.join(", "),
+            round.join(", ")
+        );
+        let parsed = parse_input(&input).unwrap();
+        let power: BigInt = format!("1{}", "0".repeat(48)).parse().unwrap();
+        let expected = Answer::from(&power + &power);
+        assert_eq!(part2(&parsed.games), Ok(expected.clone()));
+        assert_eq!(part2_from_reader(input.as_bytes()).unwrap(), expected);
+        assert_eq!(
+            minimum_bag(&parsed.games[0]).power(),
+            Ok(Answer::from(power))
+        );
+
+        // Just past a u32 and a u64
+        let parsed = parse_input("Game 1: 65536 red, 65536 green, 2 blue, 3 cyan").unwrap();
+        assert_eq!(part2(&parsed.games), Ok(Answer::from(6u64 << 32)));
+        let parsed = parse_input(
+            "Game 1: 4294967295 red, 4294967295 green, 4294967295 blue, 4294967295 cyan",
+        )
+        .unwrap();
+        assert_eq!(
+            part2(&parsed.games),
+            Ok(Answer::from((u32::MAX as u128).pow(4)))
+        );
+    }
+
+    #[test]
+    fn test_rounds() {
+        let parsed = parse_input(EXAMPLE_INPUT).unwrap();
+        let game = &parsed.games[2];
+        assert_eq!(game.id(), 3);
+        assert_eq!(game.rounds().len(), 3);
+        assert_eq!(game.rounds()[1].count(Color::GREEN), 13);
+        assert_eq!(game.round_totals(), [34, 22, 6]);
+        assert_eq!(game.rounds_over(20), [0, 1]);
+        let totals: Vec<_> = parsed.games.iter().map(|g| g.round_totals()).collect();
+        assert_eq!(totals[0], [7, 9, 2]);
+
+        // Nothing in the example repeats a colour within a round
+        assert!(parsed.games.iter().all(|g| g.repeated_colors().is_empty()));
+        let parsed =
+            parse_input("Game 7: 1 red, 2 blue, 3 red; 4 red, 4 blue, 5 blue, 1 red").unwrap();
+        let game = &parsed.games[0];
+        assert_eq!(
+            game.repeated_colors(),
+            [(0, Color::RED), (1, Color::BLUE), (1, Color::RED)]
+        );
+        // A round's bag adds repeats together, whereas the parts still look at each draw on its
+        // own, same as they always have
+        assert_eq!(game.rounds()[1].bag().get(Color::BLUE), 9);
+        assert_eq!(minimum_bag(game).get(Color::BLUE), 5);
+    }
+
+    #[test]
+    fn test_huge_rounds() {
+        let max = u32::MAX as u64;
+        let parsed = parse_input("Game 1: 4294967295 red, 1 blue").unwrap();
+        let game = &parsed.games[0];
+        assert_eq!(game.round_totals(), [max + 1]);
+        assert_eq!(game.rounds_over(max), [0]);
+        let parsed = parse_input("Game 1: 4294967295 red, 1 red").unwrap();
+        let round = &parsed.games[0].rounds()[0];
+        assert_eq!(round.count(Color::RED), max + 1);
+        assert_eq!(round.bag().get(Color::RED), max + 1);
+    }
+
+    #[test]
+    fn test_bag_inference() {
+        let game = |line| parse_input(line).unwrap().games.remove(0);
+        let limits = Bag::standard_limits();
+
+        // One red and one blue out of 12 red, 13 green and 14 blue
+        let p = probability(&game("Game 1: 1 red, 1 blue"), &limits);
+        assert!((p - (12.0 * 14.0) / (39.0 * 38.0 / 2.0)).abs() < 1e-12);
+        assert_eq!(probability(&game("Game 1: 13 red"), &limits), 0.0);
+
+        // Only ever seeing red means the bag's most likely all red, and as small as possible
+        let inferred = most_likely_bag(&game("Game 1: 2 red; 1 red"), 20).unwrap();
+        assert_eq!(inferred.bag, Bag::new().with(Color::RED, 2));
+        assert!(inferred.log_likelihood.abs() < 1e-12);
+        assert!(most_likely_bag(&game("Game 1: 2 red; 1 red"), 1).is_none());
+        // Budgets too big to search get turned away rather than searched forever
+        assert!(most_likely_bag(&game("Game 1: 2 red; 1 red"), 4_000_000_000).is_none());
+        let huge = game("Game 1: 4294967295 red, 1 blue; 4294967295 red");
+        assert!(most_likely_bag(&huge, MAX_BUDGET).is_none());
+        // The likelihood of this one keeps going up with the size of the bag, so it's searched
+        // right up to the budget
+        let inferred = most_likely_bag(&game("Game 1: 2 red; 2 blue"), MAX_BUDGET).unwrap();
+        assert!(inferred.bag.get(Color::RED) > 10_000);
+
+        // Check against trying every bag within the budget
+        let budget = 18;
+        for line in [
+            "Game 1: 1 red, 1 blue; 1 red, 1 blue",
+            "Game 2: 3 red, 1 green; 1 red, 2 blue; 2 green",
+            "Game 3: 1 red; 1 red; 1 red; 1 green",
+            "Game 4: 5 blue, 1 red; 4 blue, 2 red, 1 green",
+        ] {
+            let game = game(line);
+            let inferred = most_likely_bag(&game, budget).unwrap();
+            let mut best = f64::NEG_INFINITY;
+            for red in 0..=budget {
+                for green in 0..=budget - red {
+                    for blue in 0..=budget - red - green {
+                        let bag = Bag::new()
+                            .with(Color::RED, red)
+                            .with(Color::GREEN, green)
+                            .with(Color::BLUE, blue);
+                        best = best.max(log_likelihood(&game, &bag));
+                    }
+                }
+            }
+            assert!((inferred.log_likelihood - best).abs() < 1e-9, "{line}");
+            assert_eq!(
+                log_likelihood(&game, &inferred.bag),
+                inferred.log_likelihood
+            );
+        }
+
+        // Games 3 and 4 are impossible, so they come last
+        let games = parse_input(EXAMPLE_INPUT).unwrap().games;
+        let ranked = rank_by_plausibility(&games, &limits);
+        let ids: Vec<u32> = ranked.iter().map(|(id, _)| *id).collect();
+        assert_eq!(&ids[3..], [3, 4]);
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(ranked[4].1, 0.0);
+    }
+
+    #[test]
+    fn test_violation_report() {
+        let parsed = parse_input(EXAMPLE_INPUT).unwrap();
+        let report = violations(&parsed.games, &Bag::standard_limits());
+        let ids: Vec<u32> = report.games.iter().map(|g| g.id).collect();
+        assert_eq!(ids, [3, 4]);
+        assert_eq!(
+            report.games[1].violations[0],
+            Violation {
+                round: 2,
+                color: Color::BLUE,
+                count: 15,
+                limit: 14
+            }
+        );
+        assert_eq!(report.by_color, [(Color::RED, 2), (Color::BLUE, 1)]);
+        assert_eq!(report.most_common_cause(), Some(Color::RED));
+
+        let text = report.to_text(&parsed.palette);
+        assert_eq!(
+            text,
+            "\
+Game 3:
+  round 1: 20 red drawn, limit 12 exceeded by 8
+Game 4:
+  round 3: 15 blue drawn, limit 14 exceeded by 1
+  round 3: 14 red drawn, limit 12 exceeded by 2
+2 impossible games, most often because of red (red 2, blue 1)
+"
+        );
+        let json = report.to_json(&parsed.palette);
+        assert_eq!(
+            json.get("most_common_cause").and_then(|c| c.as_str()),
+            Some("red")
+        );
+        assert_eq!(json.to_string().parse(), Ok(json.clone()));
+        assert!(json.to_string().starts_with(
+            r#"{"games":[{"id":3,"violations":[{"round":1,"color":"red","count":20,"limit":12,"excess":8}]}"#
+        ));
+
+        let report = violations(&parsed.games, &Bag::standard_limits().with(Color::RED, 20));
+        assert!(report
+            .to_text(&parsed.palette)
+            .ends_with("most often because of blue (blue 1)\n"));
+        let report = violations(
+            &parsed.games,
+            &Bag::new()
+                .with(Color::RED, 99)
+                .with(Color::GREEN, 99)
+                .with(Color::BLUE, 99),
+        );
+        assert_eq!(report.to_text(&parsed.palette), "Every game is possible\n");
+    }
+
+    #[test]
+    fn test_big_power_queries() {
+        // Powers of about 2^192 and 2^128, which are well past what a u64 can hold
+        let max = u32::MAX;
+        let input = format!(
+            "Game 1: {max} red, {max} green, {max} blue, {max} cyan, {max} pink, {max} gold
+Game 2: {max} red, {max} green, {max} blue, {max} cyan
+Game 3: 10 red, 10 green, 10 blue"
+        );
+        let parsed = parse_input(&input).unwrap();
+        let ids = |expr| {
+            let query = parse_query(expr, &parsed.palette).unwrap();
+            let selected = select(&parsed.games, &query);
+            selected.iter().map(|game| game.id()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("power > 1000"), [1, 2]);
+        assert_eq!(ids("power >= 18446744073709551615"), [1, 2]);
+        assert_eq!(ids("power == 18446744073709551615"), Vec::<u32>::new());
+        assert_eq!(ids("power <= 1000"), [3]);
+        assert_eq!(ids("power == power and power > total"), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_queries() {
+        let parsed = parse_input(EXAMPLE_INPUT).unwrap();
+        let ids = |expr| {
+            let query = parse_query(expr, &parsed.palette).unwrap();
+            let selected = select(&parsed.games, &query);
+            selected.iter().map(|game| game.id()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("any round (red > 10 and blue < 10)"), [3]);
+        assert_eq!(ids("any round (red >= 4 and green >= 4)"), [3]);
+        // Without the brackets, the blue is for the whole game instead of the same round
+        assert_eq!(ids("any round red >= 4 and blue >= 15"), [4]);
+        assert_eq!(ids("power > 600"), [3, 4]);
+        assert_eq!(ids("max(red) = 6 or id == 1"), [1, 5]);
+        assert_eq!(ids("not all rounds total <= 10"), [3, 4]);
+        assert_eq!(ids("rounds != 3 and total > 10"), [5]);
+        assert_eq!(ids("green > red"), [2]);
+        assert_eq!(ids("not (id < 3 or id > 4)"), [3, 4]);
+
+        let error = |expr| parse_query(expr, &parsed.palette).unwrap_err();
+        assert_eq!(error("yellow > 1"), "Column 1: unknown colour \"yellow\"");
+        assert_eq!(
+            error("any game red > 1"),
+            "Column 5: expected \"round\", found \"game\""
+        );
+        assert_eq!(error("(red > 1"), "Column 9: expected \")\"");
+        assert_eq!(
+            error("red > 1 blue"),
+            "Column 9: unexpected \"blue\" after the query"
+        );
+        assert_eq!(error("red ~ 1"), "Column 5: unexpected '~'");
+        assert_eq!(
+            error("red >= <"),
+            "Column 8: expected a number, colour or property, found \"<\""
+        );
+        assert!(parse_query(
+            &("(".repeat(100) + "red > 1" + &")".repeat(100)),
+            &parsed.palette
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_query_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_query"
+        ));
+        // Queries get run against the example, and against games whose counts are as big as
+        // they go, which is where the sums and products in a query can overflow
+        let extreme = parse_input(
+            "Game 1: 4294967295 red, 4294967295 red, 1 blue; 4294967295 green\n\
+             Game 4294967295: 4294967295 blue, 4294967295 yellow, 4294967295 grün",
+        )
+        .unwrap();
+        let mut rng = Rng::new(44);
+        let game_sets: Vec<Games> = [EXAMPLE_INPUT.to_string()]
+            .into_iter()
+            .chain((0..20).map(|_| random_games(&mut rng)))
+            .filter_map(|input| parse_input(&input).ok())
+            .chain([extreme.clone()])
+            .collect();
+        check_solver(
+            fixtures,
+            &[
+                "any round (red > 10 and blue < 3)",
+                "not power >= 1000 or max(green) == 2",
+                "any round total > 1 and all rounds count(yellow) < 2",
+            ],
+            |s| parse_query(s, &extreme.palette),
+            |query| {
+                for games in &game_sets {
+                    drop(select(&games.games, query));
+                }
+            },
+        );
+    }
+
+    /// A random set of games, with any old ids, colours and counts
+    fn random_games(rng: &mut Rng) -> String {
+        const COLORS: &[&str] = &["red", "green", "blue", "yellow", "grün", "ΚΌΚΚΙΝΟ"];
+        let mut input = String::new();
+        for _ in 0..rng.below(6) {
+            let rounds: Vec<String> = (0..1 + rng.below(4))
+                .map(|_| {
+                    let draws: Vec<String> = (0..1 + rng.below(4))
+                        .map(|_| {
+                            let n = match rng.below(4) {
+                                0 => u32::MAX,
+                                _ => (rng.next_u64() >> rng.below(64)) as u32,
+                            };
+                            format!("{n} {}", rng.pick(COLORS))
+                        })
+                        .collect();
+                    draws.join(", ")
+                })
+                .collect();
+            input += &format!("Game {}: {}\n", rng.next_u64() as u32, rounds.join("; "));
+        }
+        input
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let parsed = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(render_games(&parsed), EXAMPLE_INPUT.to_string() + "\n");
+        let messy = parse_input("Game 007:3 red ,  1 blue;2 green").unwrap();
+        assert_eq!(
+            render_game(&messy.games[0], &messy.palette),
+            "Game 7: 3 red, 1 blue; 2 green"
+        );
+
+        let mut rng = Rng::new(43);
+        for _ in 0..200 {
+            let games = parse_input(&random_games(&mut rng)).unwrap();
+            let rendered = render_games(&games);
+            assert_eq!(parse_input(&rendered).as_ref(), Ok(&games), "{rendered}");
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let parsed = parse_input(EXAMPLE_INPUT).unwrap();
+        let json = to_json(&parsed);
+        assert!(json.to_string().starts_with(
+            r#"[{"id":1,"rounds":[[{"count":3,"color":"blue"},{"count":4,"color":"red"}],"#
+        ));
+
+        let mut rng = Rng::new(4343);
+        for _ in 0..200 {
+            let games = parse_input(&random_games(&mut rng)).unwrap();
+            let json = to_json(&games);
+            let reparsed: Json = json.pretty().parse().unwrap();
+            let loaded = from_json(&reparsed, Palette::standard()).unwrap();
+            assert_eq!(loaded, games);
+            assert_eq!(render_games(&loaded), render_games(&games));
+        }
+
+        // Loaded games go through the parts same as parsed ones
+        let loaded = from_json(&json, Palette::standard()).unwrap();
+        assert_eq!(part1(&loaded.games), EXPECTED_PART_1);
+        assert_eq!(part2(&loaded.games), Ok(EXPECTED_PART_2.into()));
+
+        let load = |s: &str| from_json(&s.parse().unwrap(), Palette::standard());
+        assert_eq!(
+            load("[]"),
+            Ok(Games {
+                palette: Palette::standard(),
+                games: vec![]
+            })
+        );
+        assert!(load(r#"[{"id": 1, "rounds": []}]"#).is_err());
+        assert!(load(r#"[{"id": 1, "rounds": [[]]}]"#).is_err());
+        assert!(load(r#"[{"id": 1.5, "rounds": [[{"count": 1, "color": "red"}]]}]"#).is_err());
+        assert!(load(r#"[{"id": 1, "rounds": [[{"count": 1, "color": "dark red"}]]}]"#).is_err());
+        assert!(load(r#"{"id": 1}"#).is_err());
+        let twice = r#"[{"id": 1, "rounds": [[{"count": 1, "color": "red"}]]}]"#.repeat(2);
+        assert!(load(&twice.replace("][", ",")).is_err());
+
+        // A closed palette turns away the same colours whichever way the games come in
+        let closed = Palette::standard().closed();
+        let games = parse_input_with(EXAMPLE_INPUT, closed.clone()).unwrap();
+        let loaded = from_json(&to_json(&games), closed.clone()).unwrap();
+        assert_eq!(loaded, games);
+        assert_eq!(render_games(&loaded), render_games(&games));
+        let text = "Game 1: 1 red; 2 gren";
+        let error = parse_input_with(text, closed.clone()).unwrap_err().error;
+        assert_eq!(
+            error,
+            ParseError::UnknownColor {
+                name: "gren".to_string(),
+                suggestion: Some("green".to_string())
+            }
+        );
+        let open = parse_input(text).unwrap();
+        assert_eq!(
+            from_json(&to_json(&open), closed),
+            Err(format!("Game 1: {error}"))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input| {
+            let LineError { line_no, error } = parse_input(input).unwrap_err();
+            (line_no, error)
+        };
+        assert_eq!(error("\nGame: 1 red"), (2, ParseError::MissingPrefix));
+        assert_eq!(error("Round 1: 1 red"), (1, ParseError::MissingPrefix));
+        assert_eq!(
+            error("Game one: 1 red"),
+            (1, ParseError::InvalidId("one".to_string()))
+        );
+        assert_eq!(
+            error("Game 1: 1 red; 2 dark blue"),
+            (1, ParseError::MalformedDraw("2 dark blue".to_string()))
+        );
+        assert_eq!(
+            error("Game 1: 1 red, -2 blue"),
+            (1, ParseError::MalformedCount("-2".to_string()))
+        );
+        assert_eq!(
+            error("Game 1: 1 red\nGame 2: 1 red\n\nGame 1: 2 blue"),
+            (
+                4,
+                ParseError::DuplicateId {
+                    id: 1,
+                    first_line: 1
+                }
+            )
+        );
+        let message = parse_input("Game 5: 1 red\nGame 5: 1 red")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(message, "Line 2: Game 5 was already used on line 1");
+
+        // Only closed palettes have unknown colours
+        let closed = Palette::standard().closed();
+        assert!(parse_input("Game 1: 1 gren").is_ok());
+        assert_eq!(
+            parse_input_with("Game 1: 1 red, 3 gren", closed.clone()).unwrap_err(),
+            LineError {
+                line_no: 1,
+                error: ParseError::UnknownColor {
+                    name: "gren".to_string(),
+                    suggestion: Some("green".to_string())
+                }
+            }
+        );
+        let message = parse_input_with("Game 1: 1 purple", closed)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(message, "Line 1: Unknown colour \"purple\"");
+
+        let streamed = part1_from_reader("Game 1: 1 red\nGame x: 1 red".as_bytes()).unwrap_err();
+        assert_eq!(streamed.kind(), std::io::ErrorKind::InvalidData);
+        assert!(streamed.to_string().contains("Line 2"));
+        // Streaming doesn't remember ids, so a repeated one just gets counted again
+        let streamed = part1_from_reader("Game 1: 1 red\nGame 1: 1 red".as_bytes());
+        assert_eq!(streamed.unwrap(), 2);
+
+        let parsed = parse_input("Game 1: 1 red\nGame 3: 1 red\nGame 2: 1 red\nGame 3: 1 red");
+        assert!(parsed.is_err());
+        let parsed = parse_input("Game 1: 1 red\nGame 3: 1 red\nGame 2: 1 red").unwrap();
+        assert_eq!(out_of_sequence(&parsed.games), [(3, 2), (2, 4)]);
+        assert!(out_of_sequence(&parse_input(EXAMPLE_INPUT).unwrap().games).is_empty());
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        check_solver(fixtures, &[EXAMPLE_INPUT], parse_input, |parsed| {
+            let _ = part1(&parsed.games);
+            let _ = part2(&parsed.games);
+        });
     }
 }
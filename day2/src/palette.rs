@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use common::answer::{Answer, AnswerError};
+
+/// Any word at all can be a colour, as long as it's a word
+pub fn is_color_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(char::is_alphabetic)
+}
+
+/// A cube colour, standing for its position in the `Palette` it was interned in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Color(usize);
+
+impl Color {
+    // Every palette made by `Palette::standard` starts off with these three
+    pub const RED: Color = Color(0);
+    pub const GREEN: Color = Color(1);
+    pub const BLUE: Color = Color(2);
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// All the colour names seen so far, each given its own `Color` the first time it turns up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    names: Vec<String>,
+    colors: HashMap<String, Color>,
+    /// Closed palettes don't take on new colours while parsing
+    closed: bool,
+}
+
+impl Palette {
+    /// A palette with nothing in it yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A palette already holding red, green and blue, in that order, so that the puzzle's own
+    /// colours always match up with `Color::RED` and friends
+    pub fn standard() -> Self {
+        let mut palette = Self::new();
+        for name in ["red", "green", "blue"] {
+            palette.intern(name);
+        }
+        palette
+    }
+
+    /// The colour with this name, adding it to the palette if it's new
+    pub fn intern(&mut self, name: &str) -> Color {
+        if let Some(&color) = self.colors.get(name) {
+            return color;
+        }
+        let color = Color(self.names.len());
+        self.names.push(name.to_string());
+        self.colors.insert(name.to_string(), color);
+        color
+    }
+
+    /// Stops any more colours being added while parsing, so that anything not already in the
+    /// palette is an error instead. `intern` still works, for setting things up by hand.
+    pub fn closed(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    /// The colour with this name, adding it to the palette if it's new and the palette isn't
+    /// closed
+    pub fn try_intern(&mut self, name: &str) -> Option<Color> {
+        match self.get(name) {
+            Some(color) => Some(color),
+            None if self.closed => None,
+            None => Some(self.intern(name)),
+        }
+    }
+
+    /// The name in the palette most like `name`, as long as it's only a few typos away
+    pub fn closest(&self, name: &str) -> Option<&str> {
+        let (distance, closest) = self
+            .names
+            .iter()
+            .map(|known| (edit_distance(name, known), known))
+            .min()?;
+        (distance <= 2.max(name.chars().count() / 3)).then_some(closest.as_str())
+    }
+
+    /// The colour with this name, if it's been seen before
+    pub fn get(&self, name: &str) -> Option<Color> {
+        self.colors.get(name).copied()
+    }
+
+    pub fn name(&self, color: Color) -> &str {
+        &self.names[color.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every colour in the order they were first seen
+    pub fn colors(&self) -> impl Iterator<Item = Color> {
+        (0..self.names.len()).map(Color)
+    }
+}
+
+/// How many single-char insertions, deletions and substitutions it takes to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// A number of cubes of each colour. Any colour it's never been told about has none.
+///
+/// The counts are u64s, so that a bag can hold everything from a round which draws the same
+/// colour more than once, even when each of those draws is as big as a u32 gets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bag(Vec<u64>);
+
+impl Bag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bag part 1 asks about: 12 red, 13 green and 14 blue cubes
+    pub fn standard_limits() -> Self {
+        Self(vec![12, 13, 14])
+    }
+
+    pub fn get(&self, color: Color) -> u64 {
+        self.0.get(color.0).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, color: Color, n: u64) {
+        if self.0.len() <= color.0 {
+            self.0.resize(color.0 + 1, 0);
+        }
+        self.0[color.0] = n;
+    }
+
+    pub fn with(mut self, color: Color, n: u64) -> Self {
+        self.set(color, n);
+        self
+    }
+
+    /// Written out like "12 red, 13 green", with colours in palette order
+    pub fn describe(&self, palette: &Palette) -> String {
+        let counts: Vec<String> = self
+            .counts()
+            .map(|(color, n)| format!("{n} {}", palette.name(color)))
+            .collect();
+        counts.join(", ")
+    }
+
+    /// Every colour with at least one cube, alongside how many there are
+    pub fn counts(&self) -> impl Iterator<Item = (Color, u64)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, &n)| n > 0)
+            .map(|(i, &n)| (Color(i), n))
+    }
+
+    /// The number of cubes of each colour multiplied together.
+    ///
+    /// Red, green and blue always count, so a bag missing one of them has no power, just like
+    /// in the puzzle. Any other colour only counts if the bag has some, or else one game using
+    /// yellow cubes would zero out the power of every other game.
+    ///
+    /// A handful of colours with a few hundred cubes each is already too much for a `u32`, so
+    /// this gets promoted to a bigger number whenever it needs to be.
+    pub fn power(&self) -> Result<Answer, AnswerError> {
+        let standard = [Color::RED, Color::GREEN, Color::BLUE]
+            .into_iter()
+            .map(|color| self.get(color));
+        let others = self
+            .counts()
+            .filter(|(color, _)| color.0 > Color::BLUE.0)
+            .map(|(_, n)| n);
+        standard
+            .chain(others)
+            .try_fold(Answer::one(), |power, n| power.checked_mul(n))
+    }
+}
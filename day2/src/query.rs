@@ -0,0 +1,345 @@
+//! A little language for picking out games, so questions like "which games had more than 10 red
+//! and fewer than 3 blue in the same round" don't each need their own throwaway code.
+//!
+//! ```text
+//! query   := or
+//! or      := and ("or" and)*
+//! and     := unary ("and" unary)*
+//! unary   := "not" unary | ("any" | "all") ("round" | "rounds") unary | primary
+//! primary := "(" query ")" | term op term
+//! term    := number | colour | "max" "(" colour ")" | "power" | "id" | "rounds" | "total"
+//! op      := "<" | "<=" | ">" | ">=" | "=" | "==" | "!="
+//! ```
+//!
+//! Inside `any round`/`all rounds`, a colour is how many of it were drawn in that round and
+//! `total` is how many cubes the round drew altogether. Outside of them, a colour means the same
+//! as `max(colour)`, and `total` counts every cube drawn in the game. Since `any round` only
+//! takes in the one comparison after it, it needs brackets to cover more:
+//! `any round (red > 10 and blue < 3)`.
+
+use std::fmt;
+
+use common::answer::Answer;
+
+use crate::game::{Game, Round};
+use crate::minimum_bag;
+use crate::palette::{Color, Palette};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Less => "<",
+            Op::LessOrEqual => "<=",
+            Op::Greater => ">",
+            Op::GreaterOrEqual => ">=",
+            Op::Equal => "==",
+            Op::NotEqual => "!=",
+        }
+    }
+
+    fn apply(self, a: u128, b: u128) -> bool {
+        match self {
+            Op::Less => a < b,
+            Op::LessOrEqual => a <= b,
+            Op::Greater => a > b,
+            Op::GreaterOrEqual => a >= b,
+            Op::Equal => a == b,
+            Op::NotEqual => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Number(u64),
+    /// How many of the colour were drawn in the current round, or the most in any round when
+    /// there isn't one
+    Count(Color),
+    /// The most of the colour drawn in any round
+    Max(Color),
+    Power,
+    Id,
+    Rounds,
+    /// How many cubes were drawn in the current round, or in the whole game
+    Total,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Compare(Term, Op, Term),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    AnyRound(Box<Query>),
+    AllRounds(Box<Query>),
+}
+
+impl Query {
+    pub fn matches(&self, game: &Game) -> bool {
+        self.eval(game, None)
+    }
+
+    fn eval(&self, game: &Game, round: Option<&Round>) -> bool {
+        match self {
+            Query::Compare(a, op, b) => op.apply(a.eval(game, round), b.eval(game, round)),
+            Query::And(a, b) => a.eval(game, round) && b.eval(game, round),
+            Query::Or(a, b) => a.eval(game, round) || b.eval(game, round),
+            Query::Not(q) => !q.eval(game, round),
+            Query::AnyRound(q) => game.rounds().iter().any(|r| q.eval(game, Some(r))),
+            Query::AllRounds(q) => game.rounds().iter().all(|r| q.eval(game, Some(r))),
+        }
+    }
+}
+
+impl Term {
+    /// Everything fits in a u128 apart from the biggest powers, which can only ever be compared
+    /// against numbers that fit in a u64 (or the same power), so they're capped at `u128::MAX`
+    /// without changing how any comparison comes out
+    fn eval(&self, game: &Game, round: Option<&Round>) -> u128 {
+        let max = |color| minimum_bag(game).get(color) as u128;
+        match (self, round) {
+            (Term::Number(n), _) => *n as u128,
+            (Term::Count(color), Some(round)) => round.count(*color) as u128,
+            (Term::Count(color) | Term::Max(color), _) => max(*color),
+            (Term::Power, _) => match minimum_bag(game).power() {
+                Ok(Answer::I128(n)) => n as u128,
+                Ok(Answer::U128(n)) => n,
+                _ => u128::MAX,
+            },
+            (Term::Id, _) => game.id() as u128,
+            (Term::Rounds, _) => game.rounds().len() as u128,
+            (Term::Total, Some(round)) => round.total() as u128,
+            (Term::Total, None) => game.draws().map(|(_, n)| n as u128).sum(),
+        }
+    }
+}
+
+/// Every game the query matches, in their original order
+pub fn select<'a>(games: &'a [Game], query: &Query) -> Vec<&'a Game> {
+    games.iter().filter(|game| query.matches(game)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Number(u64),
+    Open,
+    Close,
+    Op(Op),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{word:?}"),
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Open => write!(f, "\"(\""),
+            Token::Close => write!(f, "\")\""),
+            Token::Op(op) => write!(f, "\"{}\"", op.symbol()),
+        }
+    }
+}
+
+/// Each token alongside the column (counting chars from 1) it starts at
+fn tokenize(s: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let token = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c.is_alphabetic() {
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            Token::Word(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            let n = digits
+                .parse()
+                .map_err(|_| format!("Column {}: {digits} is too big", start + 1))?;
+            Token::Number(n)
+        } else {
+            let next = chars.get(i + 1).copied();
+            let (token, len) = match (c, next) {
+                ('(', _) => (Token::Open, 1),
+                (')', _) => (Token::Close, 1),
+                ('<', Some('=')) => (Token::Op(Op::LessOrEqual), 2),
+                ('<', _) => (Token::Op(Op::Less), 1),
+                ('>', Some('=')) => (Token::Op(Op::GreaterOrEqual), 2),
+                ('>', _) => (Token::Op(Op::Greater), 1),
+                ('=', Some('=')) => (Token::Op(Op::Equal), 2),
+                ('=', _) => (Token::Op(Op::Equal), 1),
+                ('!', Some('=')) => (Token::Op(Op::NotEqual), 2),
+                _ => return Err(format!("Column {}: unexpected {c:?}", start + 1)),
+            };
+            i += len;
+            token
+        };
+        tokens.push((token, start + 1));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    palette: &'a Palette,
+    /// Where the query ends, for complaining about running out of tokens
+    end: usize,
+}
+
+/// Reads a query, looking up colour names in the palette
+pub fn parse_query(s: &str, palette: &Palette) -> Result<Query, String> {
+    let mut parser = Parser {
+        tokens: tokenize(s)?,
+        pos: 0,
+        palette,
+        end: s.chars().count() + 1,
+    };
+    let query = parser.or(0)?;
+    match parser.tokens.get(parser.pos) {
+        Some((token, col)) => Err(format!("Column {col}: unexpected {token} after the query")),
+        None => Ok(query),
+    }
+}
+
+/// Deeply nested queries shouldn't be able to blow the stack
+const MAX_DEPTH: usize = 64;
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |&(_, col)| col)
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!(
+                "Column {}: expected {expected}, found {token}",
+                self.column()
+            ),
+            None => format!("Column {}: expected {expected}", self.column()),
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(w)) if w == word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token, expected: &str) -> Result<(), String> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn or(&mut self, depth: usize) -> Result<Query, String> {
+        let mut query = self.and(depth)?;
+        while self.eat_word("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and(depth)?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<Query, String> {
+        let mut query = self.unary(depth)?;
+        while self.eat_word("and") {
+            query = Query::And(Box::new(query), Box::new(self.unary(depth)?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<Query, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("a shallower query"));
+        }
+        if self.eat_word("not") {
+            return Ok(Query::Not(Box::new(self.unary(depth + 1)?)));
+        }
+        for (quantifier, wrap) in [
+            ("any", Query::AnyRound as fn(Box<Query>) -> Query),
+            ("all", Query::AllRounds),
+        ] {
+            if self.eat_word(quantifier) {
+                if !(self.eat_word("round") || self.eat_word("rounds")) {
+                    return Err(self.error("\"round\""));
+                }
+                return Ok(wrap(Box::new(self.unary(depth + 1)?)));
+            }
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let query = self.or(depth + 1)?;
+            self.expect(Token::Close, "\")\"")?;
+            return Ok(query);
+        }
+        let a = self.term()?;
+        let Some(&Token::Op(op)) = self.peek() else {
+            return Err(self.error("a comparison"));
+        };
+        self.pos += 1;
+        let b = self.term()?;
+        Ok(Query::Compare(a, op, b))
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        let term = match self.peek() {
+            Some(&Token::Number(n)) => Term::Number(n),
+            Some(Token::Word(word)) => match word.as_str() {
+                "power" => Term::Power,
+                "id" => Term::Id,
+                "rounds" => Term::Rounds,
+                "total" => Term::Total,
+                "max" => {
+                    self.pos += 1;
+                    self.expect(Token::Open, "\"(\" after max")?;
+                    let color = self.color()?;
+                    self.expect(Token::Close, "\")\"")?;
+                    return Ok(Term::Max(color));
+                }
+                _ => return self.color().map(Term::Count),
+            },
+            _ => return Err(self.error("a number, colour or property")),
+        };
+        self.pos += 1;
+        Ok(term)
+    }
+
+    fn color(&mut self) -> Result<Color, String> {
+        let Some(Token::Word(name)) = self.peek() else {
+            return Err(self.error("a colour"));
+        };
+        let color = self
+            .palette
+            .get(name)
+            .ok_or_else(|| format!("Column {}: unknown colour {name:?}", self.column()))?;
+        self.pos += 1;
+        Ok(color)
+    }
+}
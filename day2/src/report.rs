@@ -0,0 +1,155 @@
+//! Explains why impossible games are impossible, rather than just leaving them out of the sum
+
+use std::fmt::Write as _;
+
+use common::json::Json;
+
+use crate::game::Game;
+use crate::palette::{Bag, Color, Palette};
+
+/// A single draw which took out more cubes of a colour than the bag holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    /// Which round it was in, counting from 0
+    pub round: usize,
+    pub color: Color,
+    pub count: u32,
+    pub limit: u32,
+}
+
+impl Violation {
+    /// How many cubes too many were drawn
+    pub fn excess(&self) -> u32 {
+        self.count - self.limit
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpossibleGame {
+    pub id: u32,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViolationReport {
+    pub games: Vec<ImpossibleGame>,
+    /// How many violations there were of each colour's limit, most first
+    pub by_color: Vec<(Color, usize)>,
+}
+
+/// Every draw in every game that takes out more than the limits allow. Games without any are
+/// left out of the report.
+pub fn violations(games: &[Game], limits: &Bag) -> ViolationReport {
+    let mut impossible = vec![];
+    let mut by_color: Vec<(Color, usize)> = vec![];
+    for game in games {
+        let mut found = vec![];
+        for (round, draws) in game.rounds().iter().enumerate() {
+            for &(color, count) in &draws.draws {
+                // No draw can take out more than a u32's worth, so any bigger limit is never broken
+                let Ok(limit) = u32::try_from(limits.get(color)) else {
+                    continue;
+                };
+                if count > limit {
+                    found.push(Violation {
+                        round,
+                        color,
+                        count,
+                        limit,
+                    });
+                    match by_color.iter_mut().find(|(c, _)| *c == color) {
+                        Some((_, n)) => *n += 1,
+                        None => by_color.push((color, 1)),
+                    }
+                }
+            }
+        }
+        if !found.is_empty() {
+            impossible.push(ImpossibleGame {
+                id: game.id(),
+                violations: found,
+            });
+        }
+    }
+    by_color.sort_by_key(|&(color, n)| (std::cmp::Reverse(n), color));
+    ViolationReport {
+        games: impossible,
+        by_color,
+    }
+}
+
+impl ViolationReport {
+    /// The colour whose limit gets broken the most often, if any do
+    pub fn most_common_cause(&self) -> Option<Color> {
+        self.by_color.first().map(|&(color, _)| color)
+    }
+
+    /// A human-readable report, numbering rounds from 1
+    pub fn to_text(&self, palette: &Palette) -> String {
+        let mut out = String::new();
+        for game in &self.games {
+            let _ = writeln!(out, "Game {}:", game.id);
+            for v in &game.violations {
+                let _ = writeln!(
+                    out,
+                    "  round {}: {} {} drawn, limit {} exceeded by {}",
+                    v.round + 1,
+                    v.count,
+                    palette.name(v.color),
+                    v.limit,
+                    v.excess()
+                );
+            }
+        }
+        match self.most_common_cause() {
+            Some(color) => {
+                let counts: Vec<String> = self
+                    .by_color
+                    .iter()
+                    .map(|&(color, n)| format!("{} {n}", palette.name(color)))
+                    .collect();
+                let _ = writeln!(
+                    out,
+                    "{} impossible games, most often because of {} ({})",
+                    self.games.len(),
+                    palette.name(color),
+                    counts.join(", ")
+                );
+            }
+            None => out.push_str("Every game is possible\n"),
+        }
+        out
+    }
+
+    /// The same report as JSON, also numbering rounds from 1
+    pub fn to_json(&self, palette: &Palette) -> Json {
+        let color = |color| Json::String(palette.name(color).to_string());
+        let number = |n: u32| Json::Number(n as f64);
+        let games = self.games.iter().map(|game| {
+            let violations = game.violations.iter().map(|v| {
+                Json::object([
+                    ("round", Json::Number((v.round + 1) as f64)),
+                    ("color", color(v.color)),
+                    ("count", number(v.count)),
+                    ("limit", number(v.limit)),
+                    ("excess", number(v.excess())),
+                ])
+            });
+            Json::object([
+                ("id", number(game.id)),
+                ("violations", Json::Array(violations.collect())),
+            ])
+        });
+        let by_color = self.by_color.iter().map(|&(c, n)| {
+            Json::object([("color", color(c)), ("violations", Json::Number(n as f64))])
+        });
+        Json::object([
+            ("games", Json::Array(games.collect())),
+            ("by_color", Json::Array(by_color.collect())),
+            (
+                "most_common_cause",
+                self.most_common_cause().map_or(Json::Null, color),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,114 @@
+//! Writing games back out, either as puzzle-style text or as JSON, in a form that reads straight
+//! back in again
+
+use std::collections::HashMap;
+
+use common::json::Json;
+
+use crate::error::ParseError;
+use crate::game::{Game, Round};
+use crate::palette::{is_color_name, Palette};
+use crate::Games;
+
+/// The game as a line of input, like "Game 1: 3 blue, 4 red; 1 red". Draws keep their original
+/// order, but the spacing is tidied up.
+pub fn render_game(game: &Game, palette: &Palette) -> String {
+    let rounds: Vec<String> = game
+        .rounds()
+        .iter()
+        .map(|round| {
+            let draws: Vec<String> = round
+                .draws
+                .iter()
+                .map(|&(color, n)| format!("{n} {}", palette.name(color)))
+                .collect();
+            draws.join(", ")
+        })
+        .collect();
+    format!("Game {}: {}", game.id(), rounds.join("; "))
+}
+
+/// Every game, one per line, ready to go back into `parse_input`
+pub fn render_games(games: &Games) -> String {
+    let mut out = String::new();
+    for game in &games.games {
+        out.push_str(&render_game(game, &games.palette));
+        out.push('\n');
+    }
+    out
+}
+
+/// An array with an object per game, like
+/// `{"id": 1, "rounds": [[{"count": 3, "color": "blue"}, ...], ...]}`
+pub fn to_json(games: &Games) -> Json {
+    let game = |game: &Game| {
+        let rounds = game.rounds().iter().map(|round| {
+            let draws = round.draws.iter().map(|&(color, n)| {
+                Json::object([
+                    ("count", Json::Number(n as f64)),
+                    ("color", Json::String(games.palette.name(color).to_string())),
+                ])
+            });
+            Json::Array(draws.collect())
+        });
+        Json::object([
+            ("id", Json::Number(game.id() as f64)),
+            ("rounds", Json::Array(rounds.collect())),
+        ])
+    };
+    Json::Array(games.games.iter().map(game).collect())
+}
+
+/// Reads games back in from `to_json`'s format. Anything the text format couldn't hold, like
+/// a round without any draws, is rejected, so that the games always render back out properly.
+/// New colours get added onto the palette, same as `parse_input_with`, and a closed palette
+/// turns away colours it doesn't have with the same `UnknownColor` error.
+pub fn from_json(json: &Json, mut palette: Palette) -> Result<Games, String> {
+    let items = json.as_array().ok_or("Expected an array of games")?;
+    let mut games = vec![];
+    let mut seen = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let context = |msg: &str| format!("Game {}: {msg}", i + 1);
+        let id = item
+            .get("id")
+            .and_then(Json::as_u32)
+            .ok_or_else(|| context("expected a whole number \"id\""))?;
+        if let Some(first) = seen.insert(id, i + 1) {
+            return Err(context(&format!(
+                "id {id} was already used by game {first}"
+            )));
+        }
+        let rounds = item
+            .get("rounds")
+            .and_then(Json::as_array)
+            .filter(|rounds| !rounds.is_empty())
+            .ok_or_else(|| context("expected a non-empty array of \"rounds\""))?;
+        let mut parsed_rounds = vec![];
+        for round in rounds {
+            let draws = round
+                .as_array()
+                .filter(|draws| !draws.is_empty())
+                .ok_or_else(|| context("each round should be a non-empty array of draws"))?;
+            let mut parsed = Round::default();
+            for draw in draws {
+                let count = draw.get("count").and_then(Json::as_u32);
+                let color = draw.get("color").and_then(Json::as_str);
+                let (Some(n), Some(name)) = (count, color.filter(|name| is_color_name(name)))
+                else {
+                    return Err(context("each draw needs a \"count\" and a \"color\" word"));
+                };
+                let color = palette.try_intern(name).ok_or_else(|| {
+                    let error = ParseError::UnknownColor {
+                        name: name.to_string(),
+                        suggestion: palette.closest(name).map(str::to_string),
+                    };
+                    context(&error.to_string())
+                })?;
+                parsed.draws.push((color, n));
+            }
+            parsed_rounds.push(parsed);
+        }
+        games.push(Game::new(id, parsed_rounds));
+    }
+    Ok(Games { palette, games })
+}
@@ -0,0 +1,139 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Which cells around something count as touching it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Only straight up, down, left and right, so diagonal contact doesn't count
+    Orthogonal,
+    /// Anything within this many cells in every direction, diagonals included. The puzzle's
+    /// 8-neighbourhood is a radius of 1.
+    Chebyshev(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adjacency {
+    pub neighbourhood: Neighbourhood,
+    /// Whether the edges wrap around, so the left column touches the right one and the top row
+    /// touches the bottom one
+    pub wrap: bool,
+}
+
+impl Default for Adjacency {
+    /// The puzzle's rules: all 8 cells around, and nothing past the edges
+    fn default() -> Self {
+        Self {
+            neighbourhood: Neighbourhood::Chebyshev(1),
+            wrap: false,
+        }
+    }
+}
+
+impl Adjacency {
+    /// Every cell touching the run of columns `cols` on `row`, not counting the run itself,
+    /// as (x, y) pairs inside an `n_cols` by `n_rows` grid. They come row by row without
+    /// allocating anything, so this is cheap enough to call for every number.
+    ///
+    /// Each cell only turns up once, even when wrapping brings the far side of the
+    /// neighbourhood back round onto the near side, although then the rows aren't necessarily
+    /// in order any more.
+    pub fn around(
+        &self,
+        cols: Range<usize>,
+        row: usize,
+        (n_cols, n_rows): (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let Adjacency {
+            neighbourhood,
+            wrap,
+        } = *self;
+        let (x0, x1, y) = (cols.start as isize, cols.end as isize, row as isize);
+        // Reaching past the whole grid doesn't touch anything more, so the radius only ever needs
+        // to be as big as the grid
+        let radius = move |k: usize| k.min(n_cols.max(n_rows)) as isize;
+        // How far the neighbourhood reaches up and down, and then sideways on each of those rows.
+        // Wrapping round any further than the height just comes back to the same rows.
+        let reach_y = match neighbourhood {
+            Neighbourhood::Orthogonal => 1,
+            Neighbourhood::Chebyshev(k) if wrap => radius(k).min(n_rows as isize),
+            Neighbourhood::Chebyshev(k) => radius(k),
+        };
+        let reach_x = move |dy: isize| match neighbourhood {
+            Neighbourhood::Orthogonal => (dy == 0) as isize,
+            Neighbourhood::Chebyshev(k) => radius(k),
+        };
+        let place = move |n: isize, size: usize| {
+            if wrap && size > 0 {
+                Some(n.rem_euclid(size as isize) as usize)
+            } else {
+                usize::try_from(n).ok().filter(|&n| n < size)
+            }
+        };
+        // When wrapping round a short schematic, a row that an earlier one already landed on
+        // (reaching at least as far sideways) would only repeat its cells
+        let repeated = move |dy: isize| {
+            wrap && n_rows > 0
+                && (1..)
+                    .map(|laps| dy - laps * n_rows as isize)
+                    .take_while(|&earlier| earlier >= -reach_y)
+                    .any(|earlier| reach_x(earlier) >= reach_x(dy))
+        };
+        // Without wrapping, only the rows actually in the grid are worth a look
+        let dys = if wrap {
+            -reach_y..=reach_y
+        } else {
+            (-reach_y).max(-y)..=reach_y.min(n_rows as isize - 1 - y)
+        };
+
+        dys.filter(move |&dy| !repeated(dy))
+            .filter_map(move |dy| Some((reach_x(dy), place(y + dy, n_rows)?)))
+            .flat_map(move |(reach, y)| {
+                let (start, end) = (x0 - reach, x1 + reach);
+                // Likewise a row reaching further than the whole width just covers every column,
+                // and without wrapping the columns stop at the edges
+                let xs = if !wrap {
+                    start.max(0)..end.min(n_cols as isize)
+                } else if end - start > n_cols as isize {
+                    0..n_cols as isize
+                } else {
+                    start..end
+                };
+                xs.filter_map(move |x| Some((place(x, n_cols)?, y)))
+            })
+            .filter(move |&(x, y)| y != row || !cols.contains(&x))
+    }
+}
+
+/// Reads "4" or "8" for the usual neighbourhoods, or "radius=k" for everything within k cells,
+/// optionally followed by ",wrap" to join the edges up. So the puzzle's rules are "8", and
+/// "4,wrap" is a torus where diagonals don't touch.
+impl FromStr for Adjacency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (neighbourhood, wrap) = match s.split_once(',') {
+            Some((neighbourhood, "wrap")) => (neighbourhood, true),
+            Some((_, extra)) => return Err(format!("Unknown adjacency option {extra:?}")),
+            None => (s, false),
+        };
+        let neighbourhood = match neighbourhood {
+            "4" => Neighbourhood::Orthogonal,
+            "8" => Neighbourhood::Chebyshev(1),
+            _ => match neighbourhood.strip_prefix("radius=") {
+                Some(k) => Neighbourhood::Chebyshev(
+                    k.parse()
+                        .map_err(|_| format!("Invalid adjacency radius {k:?}"))?,
+                ),
+                None => {
+                    return Err(format!(
+                        "Unknown neighbourhood {neighbourhood:?}, try 4, 8 or radius=k"
+                    ))
+                }
+            },
+        };
+        Ok(Self {
+            neighbourhood,
+            wrap,
+        })
+    }
+}
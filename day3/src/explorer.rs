@@ -0,0 +1,124 @@
+use common::viewer::{Cell, Colour, Coords, Grid, Style, Viewer};
+
+use crate::schematic::{Component, Schematic};
+
+/// Interactive view over a schematic, for checking which symbols a given number is touching.
+struct SchematicExplorer {
+    /// The raw characters of the schematic, since the parsed components don't remember which
+    /// digit went where
+    chars: Vec<Vec<char>>,
+    schematic: Schematic,
+    /// The index of the currently selected number
+    selected: Option<usize>,
+}
+impl SchematicExplorer {
+    fn new(input: &str, schematic: Schematic) -> Self {
+        Self {
+            chars: input.lines().map(|l| l.chars().collect()).collect(),
+            schematic,
+            selected: None,
+        }
+    }
+
+    fn component(&self, (r, c): Coords) -> Component {
+        self.schematic.get(c, r)
+    }
+
+    /// Whether `pos` borders the selected number, without being part of it
+    fn in_selected_neighbourhood(&self, (r, c): Coords) -> bool {
+        let Some(id) = self.selected else {
+            return false;
+        };
+        self.schematic
+            .neighbours(&self.schematic.numbers()[id])
+            .any(|pos| pos == (c, r))
+    }
+}
+
+impl Grid for SchematicExplorer {
+    fn n_rows(&self) -> usize {
+        self.schematic.n_rows()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.schematic.n_cols()
+    }
+
+    fn cell(&self, pos: Coords) -> Cell {
+        let glyph = self.chars[pos.0].get(pos.1).copied().unwrap_or(' ');
+        let component = self.component(pos);
+        let selected = self
+            .selected
+            .is_some_and(|id| component == Component::Number(id));
+        let style = match component {
+            _ if selected => Style::fg(Colour::Yellow).bold(),
+            Component::Symbol(_) if self.in_selected_neighbourhood(pos) => {
+                Style::bg(Colour::Red).bold()
+            }
+            _ if self.in_selected_neighbourhood(pos) => Style::bg(Colour::Blue),
+            Component::Symbol(_) => Style::fg(Colour::Red),
+            Component::Number(_) => Style::PLAIN,
+            Component::Nothing => Style::fg(Colour::Black).bold(),
+        };
+        Cell::new(glyph, style)
+    }
+
+    fn inspect(&self, (r, c): Coords) -> String {
+        match self.component((r, c)) {
+            Component::Nothing => "Nothing".to_string(),
+            Component::Symbol(s) => {
+                let mut ids = vec![];
+                for (x, y) in self.schematic.cell_neighbours(c, r) {
+                    if let Component::Number(id) = self.schematic.get(x, y) {
+                        if !ids.contains(&id) {
+                            ids.push(id);
+                        }
+                    }
+                }
+                ids.sort();
+                let nums: Vec<u32> = ids
+                    .into_iter()
+                    .map(|id| self.schematic.numbers()[id].value)
+                    .collect();
+                format!("Symbol {s:?}, touching numbers {nums:?}")
+            }
+            Component::Number(id) => {
+                let token = &self.schematic.numbers()[id];
+                let kind = if self.schematic.is_part(token) {
+                    "part number"
+                } else {
+                    "not a part"
+                };
+                format!("Number {} (id {id}), {kind}", token.value)
+            }
+        }
+    }
+
+    fn on_key(&mut self, key: char, (r, c): Coords) -> Option<String> {
+        match key {
+            '\n' | ' ' => match self.component((r, c)) {
+                Component::Number(id) => {
+                    self.selected = Some(id);
+                    Some(format!("Selected {}", self.schematic.numbers()[id].value))
+                }
+                _ => {
+                    self.selected = None;
+                    Some("Selection cleared".to_string())
+                }
+            },
+            'x' => {
+                self.selected = None;
+                Some("Selection cleared".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        "enter/space: select number  x: clear"
+    }
+}
+
+pub fn run(input: &str, schematic: Schematic) -> std::io::Result<()> {
+    Viewer::new(SchematicExplorer::new(input, schematic)).run()
+}
@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use common::answer::{Answer, AnswerError};
+
+/// How the numbers around a gear get combined into its ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregate {
+    #[default]
+    Product,
+    Sum,
+    Max,
+}
+
+impl Aggregate {
+    /// With no numbers at all, that's 1 for a product and 0 otherwise. A gear can touch any
+    /// number of numbers, so the product gets promoted as far as it needs to go.
+    pub fn apply(self, nums: &[u32]) -> Result<Answer, AnswerError> {
+        let mut nums = nums.iter().copied();
+        match self {
+            Aggregate::Product => nums.try_fold(Answer::one(), |product, n| product.checked_mul(n)),
+            Aggregate::Sum => Answer::sum(nums),
+            Aggregate::Max => Ok(Answer::from(nums.max().unwrap_or(0))),
+        }
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(Self::Product),
+            "sum" => Ok(Self::Sum),
+            "max" => Ok(Self::Max),
+            _ => Err(format!("Unknown aggregate {s:?}, try product/sum/max")),
+        }
+    }
+}
+
+/// What counts as a gear, and how its ratio is worked out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GearRule {
+    pub symbols: Vec<char>,
+    /// The fewest numbers a gear can touch
+    pub min: usize,
+    /// The most numbers a gear can touch, if there's a limit
+    pub max: Option<usize>,
+    pub aggregate: Aggregate,
+}
+
+impl Default for GearRule {
+    /// The puzzle's gears: a '*' touching exactly two numbers, which get multiplied together
+    fn default() -> Self {
+        Self {
+            symbols: vec!['*'],
+            min: 2,
+            max: Some(2),
+            aggregate: Aggregate::Product,
+        }
+    }
+}
+
+impl GearRule {
+    /// Gears are any of the symbols, touching exactly two numbers which get multiplied
+    pub fn new(symbols: &[char]) -> Self {
+        Self {
+            symbols: symbols.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_neighbours(mut self, min: usize, max: Option<usize>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn with_aggregate(mut self, aggregate: Aggregate) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    pub fn is_gear_symbol(&self, symbol: char) -> bool {
+        self.symbols.contains(&symbol)
+    }
+
+    /// Whether a gear symbol touching this many numbers counts
+    pub fn accepts(&self, n_neighbours: usize) -> bool {
+        n_neighbours >= self.min && self.max.is_none_or(|max| n_neighbours <= max)
+    }
+}
+
+/// Reads rules of the form "symbols:count:aggregate", where count is a single number like "2",
+/// a range like "2-4", or a lower bound like "3+". So the puzzle's rule is "*:2:product", and
+/// "#:3+:sum" is any '#' touching three or more numbers, added up.
+impl FromStr for GearRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let (Some(symbols), Some(count), Some(aggregate), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!(
+                "Gear rules should look like \"symbols:count:aggregate\", not {s:?}"
+            ));
+        };
+        let symbols: Vec<char> = symbols.chars().collect();
+        if symbols.is_empty() {
+            return Err("A gear rule needs at least one symbol".to_string());
+        }
+        let number = |n: &str| {
+            n.parse::<usize>()
+                .map_err(|_| format!("Invalid neighbour count {n:?}"))
+        };
+        let (min, max) = if let Some(min) = count.strip_suffix('+') {
+            (number(min)?, None)
+        } else if let Some((min, max)) = count.split_once('-') {
+            (number(min)?, Some(number(max)?))
+        } else {
+            (number(count)?, Some(number(count)?))
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("The neighbour count {count:?} is empty"));
+        }
+        Ok(Self::new(&symbols)
+            .with_neighbours(min, max)
+            .with_aggregate(aggregate.parse()?))
+    }
+}
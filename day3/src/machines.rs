@@ -0,0 +1,174 @@
+//! Groups the schematic into machines: numbers and symbols that are all joined up through
+//! touching each other, directly or through a chain of other parts.
+
+use std::collections::HashMap;
+
+use crate::schematic::{NumberToken, Schematic};
+
+/// The smallest box holding every cell of a machine, with the corners included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+impl Bounds {
+    pub fn width(&self) -> usize {
+        self.right - self.left + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.bottom - self.top + 1
+    }
+}
+
+/// A group of numbers and symbols joined up through touching. There's always at least one of
+/// each, since a number on its own is isolated, and symbols on their own don't do anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+    /// In reading order
+    pub numbers: Vec<NumberToken>,
+    /// Each symbol alongside its (x, y) position, in reading order
+    pub symbols: Vec<((usize, usize), char)>,
+    /// With wrap-around edges this is just the furthest cells apart, so a machine joined up
+    /// across an edge can take up the whole width or height
+    pub bounds: Bounds,
+}
+
+impl Machine {
+    /// All of its numbers added up
+    pub fn total(&self) -> u64 {
+        self.numbers.iter().map(|token| token.value as u64).sum()
+    }
+
+    pub fn largest(&self) -> u32 {
+        self.numbers
+            .iter()
+            .map(|token| token.value)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machines {
+    /// In reading order of their first number
+    pub machines: Vec<Machine>,
+    /// Numbers which aren't touching any symbols, so aren't part of anything
+    pub isolated: Vec<NumberToken>,
+}
+
+/// Union-find over the numbers followed by the symbols
+struct Groups {
+    parents: Vec<usize>,
+}
+
+impl Groups {
+    fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        // Keeping the smaller index as the root means each group's root is its first number
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+/// The machine for the group, with its bounds stretched to cover the cells from (left, top) to
+/// (right, bottom)
+fn machine_for(
+    by_root: &mut HashMap<usize, Machine>,
+    root: usize,
+    (left, top, right, bottom): (usize, usize, usize, usize),
+) -> &mut Machine {
+    let machine = by_root.entry(root).or_insert_with(|| Machine {
+        numbers: vec![],
+        symbols: vec![],
+        bounds: Bounds {
+            left,
+            top,
+            right,
+            bottom,
+        },
+    });
+    let bounds = &mut machine.bounds;
+    bounds.left = bounds.left.min(left);
+    bounds.top = bounds.top.min(top);
+    bounds.right = bounds.right.max(right);
+    bounds.bottom = bounds.bottom.max(bottom);
+    machine
+}
+
+/// Splits the schematic up into machines, using its adjacency for what counts as touching.
+/// Numbers always join up with the symbols they touch, and with `link_symbols` symbols touching
+/// each other join up too, so two machines connected by a chain of symbols become one.
+pub fn machines(schematic: &Schematic, link_symbols: bool) -> Machines {
+    let numbers = schematic.numbers();
+    let symbols = schematic.symbols();
+    let symbol_ids: HashMap<(usize, usize), usize> = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, &(pos, _))| (pos, numbers.len() + i))
+        .collect();
+    let mut groups = Groups::new(numbers.len() + symbols.len());
+    let mut is_part = vec![false; numbers.len()];
+    for (id, token) in numbers.iter().enumerate() {
+        for pos in schematic.neighbours(token) {
+            if let Some(&symbol) = symbol_ids.get(&pos) {
+                groups.join(id, symbol);
+                is_part[id] = true;
+            }
+        }
+    }
+    if link_symbols {
+        for &((x, y), _) in &symbols {
+            for pos in schematic.cell_neighbours(x, y) {
+                if let Some(&other) = symbol_ids.get(&pos) {
+                    groups.join(symbol_ids[&(x, y)], other);
+                }
+            }
+        }
+    }
+
+    // Only groups with a number at the root have any numbers at all
+    let mut by_root: HashMap<usize, Machine> = HashMap::new();
+    let mut isolated = vec![];
+    for (id, token) in numbers.iter().enumerate() {
+        if !is_part[id] {
+            isolated.push(*token);
+            continue;
+        }
+        let cells = (token.start_col, token.row, token.end_col - 1, token.row);
+        machine_for(&mut by_root, groups.find(id), cells)
+            .numbers
+            .push(*token);
+    }
+    for (i, &((x, y), symbol)) in symbols.iter().enumerate() {
+        let root = groups.find(numbers.len() + i);
+        if root < numbers.len() {
+            machine_for(&mut by_root, root, (x, y, x, y))
+                .symbols
+                .push(((x, y), symbol));
+        }
+    }
+
+    let mut machines: Vec<(usize, Machine)> = by_root.into_iter().collect();
+    machines.sort_by_key(|&(root, _)| root);
+    Machines {
+        machines: machines.into_iter().map(|(_, machine)| machine).collect(),
+        isolated,
+    }
+}
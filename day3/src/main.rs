@@ -1,147 +1,197 @@
+mod adjacency;
+mod explorer;
+mod gears;
+mod machines;
+mod schematic;
+
+use adjacency::Adjacency;
+use common::answer::{Answer, AnswerError};
+use common::perf::{self, CountingAlloc, Recorder};
+use gears::GearRule;
+use schematic::{parse_input, parse_input_with, Backend, Schematic};
+use std::collections::HashMap;
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("bench-sparse") {
+        let size = std::env::args().nth(2).map_or(10_000, |n| {
+            n.parse()
+                .expect("The schematic size should be a whole number of rows")
+        });
+        bench_sparse(size);
+        return;
+    }
+
     let raw_input = include_str!("../input");
-    let parsed_input = parse_input(raw_input);
-    println!("Part 1: {}", part1(&parsed_input));
-    println!("Part 2: {}", part2(&parsed_input));
-}
+    let parsed_input = parse_input(raw_input).expect("Couldn't parse the input");
 
-fn part1(schematic: &Schematic) -> u32 {
-    // Find all numbers which are neighbours with a symbol
-    let mut numbers: Vec<(usize, u32)> = vec![];
-    for (y, row) in schematic.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            if let Component::Number { id, value } = cell {
-                if any_neighbour_symbols(&schematic, x, y) {
-                    numbers.push((*id, *value));
-                }
-            }
-        }
+    if let Some(iterations) = perf::bench_iterations() {
+        let mut recorder = Recorder::new(3);
+        recorder.bench(1, iterations, || part1(&parsed_input));
+        recorder.bench(2, iterations, || part2(&parsed_input));
+        recorder
+            .save()
+            .expect("Couldn't save the benchmark results");
+        return;
     }
-    numbers.sort();
-    // Dedup to remove duplicate IDs, to prevent double-counting
-    numbers.dedup();
-    numbers.into_iter().map(|(_, val)| val).sum()
-}
 
-fn part2(schematic: &Schematic) -> u32 {
-    let mut num_pairs: Vec<Vec<u32>> = vec![];
-    // Find all '*'s which have 2 surrounding numbers, and put those nums in a big list
-    for (y, row) in schematic.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            if let Component::Symbol('*') = cell {
-                let neighbor_nums = neighbour_nums(schematic, x, y);
-                if neighbor_nums.len() == 2 {
-                    num_pairs.push(neighbor_nums);
-                }
+    if std::env::args().nth(1).as_deref() == Some("explore") {
+        explorer::run(raw_input, parsed_input).expect("Failed to run the explorer");
+        return;
+    }
+
+    // Part 2 can be asked about other kinds of gear with `--gears symbols:count:aggregate`, and
+    // both parts can change what counts as touching with `--adjacency 4,wrap` and the like.
+    // `--machines` lists which parts are joined up, with `--link-symbols` letting symbols join
+    // up through each other too.
+    let mut rule = GearRule::default();
+    let mut adjacency = Adjacency::default();
+    let mut show_machines = false;
+    let mut link_symbols = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--gears" => {
+                rule = args
+                    .next()
+                    .expect("--gears needs a rule, like \"#:3+:sum\"")
+                    .parse()
+                    .unwrap_or_else(|e| panic!("{e}"));
+            }
+            "--adjacency" => {
+                adjacency = args
+                    .next()
+                    .expect("--adjacency needs a neighbourhood, like \"4\" or \"radius=2,wrap\"")
+                    .parse()
+                    .unwrap_or_else(|e| panic!("{e}"));
             }
+            "--machines" => show_machines = true,
+            "--link-symbols" => link_symbols = true,
+            _ => panic!("Unknown argument {arg:?}"),
         }
     }
-    num_pairs
-        .into_iter()
-        .map(|v| v.into_iter().product::<u32>())
-        .sum()
+    let schematic = parsed_input.with_adjacency(adjacency);
+    println!("Part 1: {}", part1(&schematic).unwrap());
+    println!("Part 2: {}", gear_total(&schematic, &rule).unwrap());
+    if show_machines {
+        print_machines(&machines::machines(&schematic, link_symbols));
+    }
 }
 
-fn any_neighbour_symbols(schematic: &Schematic, x: usize, y: usize) -> bool {
-    assert!(match schematic[y][x] {
-        Component::Number { .. } => true,
-        _ => false,
-    });
-
-    for (x, y) in neighbours(x, y) {
-        if let Some(Component::Symbol(_)) = schematic.get(y).and_then(|v| v.get(x)) {
-            return true;
-        }
+fn print_machines(found: &machines::Machines) {
+    for (i, machine) in found.machines.iter().enumerate() {
+        let symbols: String = machine.symbols.iter().map(|&(_, symbol)| symbol).collect();
+        let b = machine.bounds;
+        println!(
+            "Machine {}: {} numbers totalling {} (largest {}), symbols {symbols:?}, \
+             {}x{} from ({}, {}) to ({}, {})",
+            i + 1,
+            machine.numbers.len(),
+            machine.total(),
+            machine.largest(),
+            b.width(),
+            b.height(),
+            b.left,
+            b.top,
+            b.right,
+            b.bottom
+        );
     }
-    false
+    let isolated: Vec<String> = found.isolated.iter().map(|t| t.value.to_string()).collect();
+    println!(
+        "{} machines, {} isolated numbers: {}",
+        found.machines.len(),
+        isolated.len(),
+        isolated.join(", ")
+    );
 }
 
-/// Gets a Vec of any numbers surrounding a set of coords, already de-duplicated
-fn neighbour_nums(schematic: &Schematic, x: usize, y: usize) -> Vec<u32> {
-    assert!(match schematic[y][x] {
-        Component::Symbol('*') => true,
-        _ => false,
-    });
-    let mut nums = vec![];
-    for (x, y) in neighbours(x, y) {
-        if let Some(Component::Number { id, value }) = schematic.get(y).and_then(|v| v.get(x)) {
-            nums.push((id, value));
-        }
+/// Compares the dense and sparse backends on a generated square schematic of the given size
+fn bench_sparse(size: usize) {
+    let input = schematic::generate_schematic(size, 1);
+    println!("{size}x{size} schematic, {} MB", input.len() >> 20);
+    for (name, backend) in [("dense", Backend::Dense), ("sparse", Backend::Sparse)] {
+        let parse = || parse_input_with(&input, backend).expect("The generated input should parse");
+        let (parse_time, parse_allocations) = perf::bench(5, parse);
+        let schematic = parse();
+        let (solve_time, solve_allocations) =
+            perf::bench(5, || (part1(&schematic), part2(&schematic)));
+        println!(
+            "{name:<6} parse {:>10} ({parse_allocations} allocations), parts 1 and 2 {:>10} \
+             ({solve_allocations} allocations), answers {} and {}",
+            perf::format_duration(parse_time),
+            perf::format_duration(solve_time),
+            part1(&schematic).unwrap(),
+            part2(&schematic).unwrap()
+        );
     }
-    nums.sort();
-    nums.dedup();
-    Vec::from_iter(nums.into_iter().map(|(_, n)| *n))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Component {
-    Nothing,
-    Symbol(char),
-    Number { id: usize, value: u32 },
+fn part1(schematic: &Schematic) -> Result<Answer, AnswerError> {
+    // Add up all the numbers which are neighbours with a symbol
+    Answer::sum(
+        schematic
+            .numbers()
+            .iter()
+            .filter(|token| schematic.is_part(token))
+            .map(|token| token.value),
+    )
 }
 
-type Schematic = Vec<Vec<Component>>;
-
-fn parse_input(input: &str) -> Schematic {
-    let mut v: Vec<Vec<Component>> = vec![];
-    let mut id = 0;
-    for (_, line) in input.lines().enumerate() {
-        let mut row = vec![];
-        let mut digits_to_skip = 0;
-        for (x, chr) in line.chars().enumerate() {
-            if digits_to_skip > 0 {
-                // For skipping over the digits of numbers we just parsed
-                digits_to_skip -= 1;
-                continue;
-            } else if chr.is_ascii_digit() {
-                // For parsing numbers
-                let value = line[x..]
-                    .chars()
-                    .map_while(|c| c.to_digit(10))
-                    .fold(0, |agg, i| 10 * agg + i);
-
-                let number_length = value.ilog10() + 1;
-                for _ in 0..number_length {
-                    row.push(Component::Number { id, value });
-                }
-                id += 1;
-                digits_to_skip = number_length - 1;
-            } else if chr == '.' {
-                // For parsing nothingness
-                row.push(Component::Nothing);
-            } else {
-                // All else are symbols
-                row.push(Component::Symbol(chr));
+fn part2(schematic: &Schematic) -> Result<Answer, AnswerError> {
+    gear_total(schematic, &GearRule::default())
+}
+
+/// Adds up the ratio of every gear, where the rule decides what a gear is and what its ratio is
+fn gear_total(schematic: &Schematic, rule: &GearRule) -> Result<Answer, AnswerError> {
+    // Each number gets added to the gears around it, looking them up straight from the cells
+    // it borders. Numbers only visit each cell around them once, so there's nothing to dedup.
+    let mut gears: HashMap<(usize, usize), Vec<u32>> = HashMap::new();
+    if rule.accepts(0) {
+        // Then even gears touching nothing count, so they all need to be there from the start
+        for (pos, symbol) in schematic.symbols() {
+            if rule.is_gear_symbol(symbol) {
+                gears.insert(pos, vec![]);
             }
         }
-        v.push(row)
     }
-
-    #[cfg(debug_assertions)]
-    {
-        let len = v[0].len();
-        assert!(v.iter().all(|v| v.len() == len));
-    }
-
-    v
-}
-
-/// Get all neighbouring pairs of coords. Returns pairs of the form (x, y)
-fn neighbours(x: usize, y: usize) -> Vec<(usize, usize)> {
-    let mut v = vec![];
-    for i in (x.saturating_sub(1))..=(x.saturating_add(1)) {
-        for j in (y.saturating_sub(1))..=(y.saturating_add(1)) {
-            if i != x || j != y {
-                v.push((i, j));
+    for token in schematic.numbers() {
+        for (x, y) in schematic.neighbours(token) {
+            if schematic
+                .symbol_at(x, y)
+                .is_some_and(|symbol| rule.is_gear_symbol(symbol))
+            {
+                gears.entry((x, y)).or_default().push(token.value);
             }
         }
     }
-    v
+    gears
+        .values()
+        .filter(|nums| rule.accepts(nums.len()))
+        .try_fold(Answer::zero(), |total, nums| {
+            total.checked_add(rule.aggregate.apply(nums)?)
+        })
 }
 
 #[cfg(test)]
 mod day3_tests {
-    use crate::{parse_input, part1, part2};
+    use std::ops::Range;
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
+
+    use common::fuzz::Rng;
+
+    use crate::adjacency::{Adjacency, Neighbourhood};
+    use crate::gears::{Aggregate, GearRule};
+    use crate::machines::{machines, Bounds};
+    use crate::schematic::{
+        generate_schematic, parse_input, parse_input_with, Backend, Component, NumberToken,
+    };
+    use crate::{gear_total, part1, part2};
 
     const EXAMPLE_INPUT_DATA: &'static str = "\
 467..114..
@@ -158,16 +208,430 @@ mod day3_tests {
 
     const EXPECTED_PART_1: u32 = 4361;
 
-    const EXPECTED_PART_2: u32 = 467835;
+    const EXPECTED_PART_2: u64 = 467835;
 
     #[test]
     fn test_part1() {
-        let input = parse_input(EXAMPLE_INPUT_DATA);
-        assert_eq!(part1(&input), EXPECTED_PART_1)
+        let input = parse_input(EXAMPLE_INPUT_DATA).unwrap();
+        assert_eq!(part1(&input), Ok(Answer::from(EXPECTED_PART_1)));
     }
     #[test]
     fn test_part2() {
-        let input = parse_input(EXAMPLE_INPUT_DATA);
-        assert_eq!(part2(&input), EXPECTED_PART_2)
+        let input = parse_input(EXAMPLE_INPUT_DATA).unwrap();
+        assert_eq!(part2(&input), Ok(Answer::from(EXPECTED_PART_2)));
+    }
+
+    #[test]
+    fn test_big_numbers() {
+        let max = u32::MAX as u64;
+        let input = parse_input("4294967295*1").unwrap();
+        assert_eq!(part1(&input), Ok(Answer::from(max + 1)));
+        assert_eq!(part2(&input), Ok(Answer::from(max)));
+        // Three of the biggest numbers around one gear is too much for even a u64
+        let input = parse_input("4294967295.4294967295\n..........*4294967295").unwrap();
+        let product = Answer::from(max).checked_pow(3).unwrap();
+        let total = |rule: &str| gear_total(&input, &rule.parse().unwrap());
+        assert_eq!(total("*:3:product"), Ok(product));
+        assert_eq!(total("*:3:sum"), Ok(Answer::from(max * 3)));
+        // And the gear totals themselves can add up past a u64
+        let row = "4294967295*4294967295.".repeat(3);
+        let gap = ".".repeat(row.len());
+        let input = parse_input(&format!("{row}\n{gap}\n{row}")).unwrap();
+        let product = Answer::from(max).checked_pow(2).unwrap();
+        let expected = Answer::sum(std::iter::repeat_n(product, 6)).unwrap();
+        assert_eq!(part2(&input), Ok(expected));
+    }
+
+    #[test]
+    fn test_gear_rules() {
+        let input = parse_input(EXAMPLE_INPUT_DATA).unwrap();
+        let total = |rule: &str| gear_total(&input, &rule.parse().unwrap()).unwrap();
+        assert_eq!(total("*:2:product"), Answer::from(EXPECTED_PART_2));
+        // The '*' next to 617 on its own counts now
+        assert_eq!(total("*:1-2:sum"), Answer::from(467 + 35 + 617 + 755 + 598));
+        assert_eq!(total("*:1:max"), Answer::from(617));
+        assert_eq!(total("#+$:1+:sum"), Answer::from(633 + 592 + 664));
+        assert_eq!(total("*#:3+:sum"), Answer::from(0));
+        // Gears touching no numbers at all still count when the rule allows it
+        assert_eq!(total("$#:0:product"), Answer::from(0));
+        assert_eq!(total("*+:0-1:max"), Answer::from(617 + 592));
+        assert_eq!(
+            gear_total(
+                &parse_input("*..\n..*").unwrap(),
+                &"*:0:product".parse().unwrap()
+            ),
+            Ok(Answer::from(2))
+        );
+
+        let rule = GearRule::new(&['*', '#']).with_aggregate(Aggregate::Max);
+        assert_eq!(rule, "*#:2:max".parse().unwrap());
+        assert_eq!(
+            "#:3+:sum".parse(),
+            Ok(GearRule::new(&['#'])
+                .with_neighbours(3, None)
+                .with_aggregate(Aggregate::Sum))
+        );
+        for bad in [
+            "*:2",
+            ":2:sum",
+            "*:x:sum",
+            "*:3-2:sum",
+            "*:2:mean",
+            "*:2:sum:",
+        ] {
+            assert!(bad.parse::<GearRule>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_number_spans() {
+        let input = parse_input("007.0*\n.....1\n10....").unwrap();
+        let token = |row, start_col, end_col, value| NumberToken {
+            row,
+            start_col,
+            end_col,
+            value,
+        };
+        assert_eq!(
+            input.numbers(),
+            [
+                token(0, 0, 3, 7),
+                token(0, 4, 5, 0),
+                token(1, 5, 6, 1),
+                token(2, 0, 2, 10)
+            ]
+        );
+        // The leading zeros put 007 out of reach of the '*', while 0 is right next to it
+        assert_eq!(part1(&input), Ok(Answer::from(1)));
+        assert_eq!(part2(&input), Ok(Answer::from(0)));
+        assert_eq!(
+            gear_total(&input, &"*:2:sum".parse().unwrap()),
+            Ok(Answer::from(1))
+        );
+
+        let input = parse_input("1*1\n*0*\n1*1").unwrap();
+        assert_eq!(part1(&input), Ok(Answer::from(4)));
+        // Each number only counts once per gear, however many of its digits touch it
+        let input = parse_input("123\n.*.\n4..").unwrap();
+        assert_eq!(part2(&input), Ok(Answer::from(123 * 4)));
+    }
+
+    #[test]
+    fn test_unicode_symbols() {
+        // Two-byte '§' and 'é', three-byte '€' and four-byte '🎲' all take up one column each
+        let input = parse_input("§12..€\n....3.\n.é..🎲7\n45....").unwrap();
+        let spans: Vec<_> = input
+            .numbers()
+            .iter()
+            .map(|t| (t.row, t.start_col, t.end_col, t.value))
+            .collect();
+        assert_eq!(
+            spans,
+            [(0, 1, 3, 12), (1, 4, 5, 3), (2, 5, 6, 7), (3, 0, 2, 45)]
+        );
+        assert_eq!(part1(&input), Ok(Answer::from(12 + 3 + 7 + 45)));
+        assert_eq!(
+            gear_total(&input, &"€:1+:sum".parse().unwrap()),
+            Ok(Answer::from(3))
+        );
+        assert_eq!(
+            gear_total(&input, &"🎲:2:product".parse().unwrap()),
+            Ok(Answer::from(3 * 7))
+        );
+
+        // Same width in chars, even though the byte lengths are all different
+        assert!(parse_input("ü.\n..\n🎲ß").is_ok());
+        assert_eq!(
+            parse_input("é1\n..."),
+            Err("Line 2: expected 2 columns like the first line, found 3".to_string())
+        );
+        assert_eq!(
+            parse_input("€€99999999999"),
+            Err("Line 1: number too big at column 3".to_string())
+        );
+    }
+
+    /// Parses the input both ways, checking that they agree on everything
+    fn check_backends(input: &str) {
+        let dense = parse_input_with(input, Backend::Dense).unwrap();
+        let sparse = parse_input_with(input, Backend::Sparse).unwrap();
+        assert_eq!(dense.numbers(), sparse.numbers());
+        assert_eq!(dense.symbols(), sparse.symbols());
+        assert_eq!(
+            (dense.n_rows(), dense.n_cols()),
+            (sparse.n_rows(), sparse.n_cols())
+        );
+        // Including a ring of cells just off the edge
+        for y in 0..dense.n_rows() + 1 {
+            for x in 0..dense.n_cols() + 1 {
+                assert_eq!(dense.get(x, y), sparse.get(x, y), "({x}, {y}) in {input:?}");
+                assert_eq!(dense.symbol_at(x, y), sparse.symbol_at(x, y));
+            }
+        }
+        assert_eq!(part1(&dense), part1(&sparse));
+        for rule in ["*:2:product", "*#:1+:sum", "$+@:1-3:max"] {
+            let rule = rule.parse().unwrap();
+            assert_eq!(gear_total(&dense, &rule), gear_total(&sparse, &rule));
+        }
+    }
+
+    #[test]
+    fn test_sparse_backend() {
+        let input = parse_input_with(EXAMPLE_INPUT_DATA, Backend::Sparse).unwrap();
+        assert_eq!(part1(&input), Ok(Answer::from(EXPECTED_PART_1)));
+        assert_eq!(part2(&input), Ok(Answer::from(EXPECTED_PART_2)));
+        assert_eq!(input.get(1, 0), Component::Number(0));
+        assert_eq!(input.get(3, 0), Component::Nothing);
+        assert_eq!(input.get(6, 3), Component::Symbol('#'));
+
+        check_backends(EXAMPLE_INPUT_DATA);
+        check_backends("");
+        check_backends("1*1\n*0*\n1*1");
+        check_backends("§12..€\n....3.\n.é..🎲7\n45....");
+        let mut rng = Rng::new(3);
+        for _ in 0..50 {
+            let size = 1 + rng.below(30);
+            check_backends(&generate_schematic(size, rng.next_u64()));
+        }
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let with = |input: &str, adjacency: &str| {
+            parse_input(input)
+                .unwrap()
+                .with_adjacency(adjacency.parse().unwrap())
+        };
+        // Only touching diagonally
+        let input = "1..\n.*.\n..2";
+        assert_eq!(part1(&with(input, "8")), Ok(Answer::from(3)));
+        assert_eq!(part2(&with(input, "8")), Ok(Answer::from(2)));
+        assert_eq!(part1(&with(input, "4")), Ok(Answer::from(0)));
+        assert_eq!(part2(&with(input, "4")), Ok(Answer::from(0)));
+        let input = "1*2\n...";
+        assert_eq!(part2(&with(input, "4")), Ok(Answer::from(2)));
+
+        assert_eq!(part1(&with("1.*", "8")), Ok(Answer::from(0)));
+        assert_eq!(part1(&with("1.*", "radius=2")), Ok(Answer::from(1)));
+        assert_eq!(part1(&with("1..*\n....", "8")), Ok(Answer::from(0)));
+        assert_eq!(part1(&with("1..*\n....", "8,wrap")), Ok(Answer::from(1)));
+        // Top and bottom join up too
+        assert_eq!(
+            part1(&with("..5.\n....\n..#.", "4,wrap")),
+            Ok(Answer::from(5))
+        );
+        assert_eq!(part1(&with("..5.\n....\n..#.", "4")), Ok(Answer::from(0)));
+        // Wrapping round a tiny schematic reaches the same numbers from both sides, but they
+        // still only count once
+        assert_eq!(part2(&with("1*2", "radius=2,wrap")), Ok(Answer::from(2)));
+        assert_eq!(
+            with("1*2", "radius=2,wrap")
+                .cell_neighbours(1, 0)
+                .collect::<Vec<_>>(),
+            [(0, 0), (2, 0)]
+        );
+
+        assert_eq!(
+            "radius=3,wrap".parse(),
+            Ok(Adjacency {
+                neighbourhood: Neighbourhood::Chebyshev(3),
+                wrap: true
+            })
+        );
+        assert_eq!("8".parse(), Ok(Adjacency::default()));
+        for bad in ["", "6", "radius=", "radius=-1", "4,wrapped", "4,wrap,wrap"] {
+            assert!(bad.parse::<Adjacency>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_adjacency_brute_force() {
+        // Going straight from the definitions, with distances measured the short way round
+        // when wrapping
+        let touching = |adjacency: Adjacency, cols: Range<usize>, row, (w, h): (usize, usize)| {
+            let distance = |a: usize, b: usize, size: usize| {
+                let d = a.abs_diff(b);
+                if adjacency.wrap {
+                    d.min(size - d)
+                } else {
+                    d
+                }
+            };
+            let mut cells = vec![];
+            for y in 0..h {
+                for x in 0..w {
+                    let touches = !(y == row && cols.contains(&x))
+                        && cols.clone().any(|col| {
+                            let (dx, dy) = (distance(x, col, w), distance(y, row, h));
+                            match adjacency.neighbourhood {
+                                Neighbourhood::Orthogonal => dx + dy == 1,
+                                Neighbourhood::Chebyshev(k) => dx.max(dy) <= k,
+                            }
+                        });
+                    if touches {
+                        cells.push((x, y));
+                    }
+                }
+            }
+            cells
+        };
+        for spec in [
+            "4",
+            "8",
+            "radius=0",
+            "radius=2",
+            "4,wrap",
+            "8,wrap",
+            "radius=3,wrap",
+            // Far bigger than any of the grids, which used to mean looking at every cell in
+            // reach whether it was on the grid or not
+            "radius=1000000",
+            "radius=1000000,wrap",
+            "radius=18446744073709551615,wrap",
+        ] {
+            let adjacency: Adjacency = spec.parse().unwrap();
+            for (w, h) in (1..7).flat_map(|w| (1..6).map(move |h| (w, h))) {
+                for row in 0..h {
+                    for start in 0..w {
+                        for end in start + 1..=w {
+                            let mut found: Vec<_> =
+                                adjacency.around(start..end, row, (w, h)).collect();
+                            let n_found = found.len();
+                            found.sort_by_key(|&(x, y)| (y, x));
+                            found.dedup();
+                            assert_eq!(n_found, found.len(), "{spec} repeats cells");
+                            assert_eq!(
+                                found,
+                                touching(adjacency, start..end, row, (w, h)),
+                                "{spec} around {start}..{end} on row {row} of {w}x{h}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adjacency_is_symmetric() {
+        // Gears look for numbers from the numbers' side, so looking from the symbols' side
+        // should find exactly the same ones, whatever the neighbourhood
+        let mut rng = Rng::new(4);
+        for adjacency in ["4", "8", "radius=2", "4,wrap", "8,wrap", "radius=3,wrap"] {
+            for _ in 0..10 {
+                let size = 1 + rng.below(12);
+                let input = generate_schematic(size, rng.next_u64());
+                let schematic = parse_input(&input)
+                    .unwrap()
+                    .with_adjacency(adjacency.parse().unwrap());
+                for ((x, y), _) in schematic.symbols() {
+                    let mut from_symbol: Vec<usize> = schematic
+                        .cell_neighbours(x, y)
+                        .filter_map(|(x, y)| match schematic.get(x, y) {
+                            Component::Number(id) => Some(id),
+                            _ => None,
+                        })
+                        .collect();
+                    from_symbol.sort();
+                    from_symbol.dedup();
+                    let from_numbers: Vec<usize> = (0..schematic.numbers().len())
+                        .filter(|&id| {
+                            schematic
+                                .neighbours(&schematic.numbers()[id])
+                                .any(|pos| pos == (x, y))
+                        })
+                        .collect();
+                    assert_eq!(from_symbol, from_numbers, "{adjacency} {input:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_machines() {
+        let input = parse_input(EXAMPLE_INPUT_DATA).unwrap();
+        let found = machines(&input, false);
+        let isolated: Vec<u32> = found.isolated.iter().map(|t| t.value).collect();
+        assert_eq!(isolated, [114, 58]);
+        let summary: Vec<(Vec<u32>, String)> = found
+            .machines
+            .iter()
+            .map(|m| {
+                let numbers = m.numbers.iter().map(|t| t.value).collect();
+                (numbers, m.symbols.iter().map(|&(_, c)| c).collect())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (vec![467, 35], "*".to_string()),
+                (vec![633], "#".to_string()),
+                (vec![617], "*".to_string()),
+                (vec![592], "+".to_string()),
+                (vec![755, 598], "*".to_string()),
+                (vec![664], "$".to_string()),
+            ]
+        );
+        assert_eq!(
+            found.machines[0].bounds,
+            Bounds {
+                left: 0,
+                top: 0,
+                right: 3,
+                bottom: 2
+            }
+        );
+        assert_eq!(found.machines[4].bounds.width(), 4);
+        assert_eq!(found.machines[4].bounds.height(), 3);
+        assert_eq!(found.machines[4].largest(), 755);
+        // Every part number is in exactly one machine
+        let total: u64 = found.machines.iter().map(|m| m.total()).sum();
+        assert_eq!(total, EXPECTED_PART_1 as u64);
+
+        // Numbers can chain machines together through a shared symbol, and symbols can chain
+        // through each other once they're allowed to
+        let input = parse_input("1*.....3\n..@@@@#.\n4.......").unwrap();
+        let found = machines(&input, false);
+        assert_eq!(found.machines.len(), 2);
+        assert_eq!(found.isolated, [input.numbers()[2]]);
+        let joined = machines(&input, true);
+        assert_eq!(joined.machines.len(), 1);
+        assert_eq!(joined.machines[0].total(), 1 + 3);
+        assert_eq!(joined.machines[0].symbols.len(), 6);
+        assert_eq!(joined.machines[0].bounds.width(), 8);
+        // Symbols that only touch each other aren't a machine
+        let input = parse_input("##..\n...5").unwrap();
+        let found = machines(&input, true);
+        assert!(found.machines.is_empty());
+        assert_eq!(found.isolated.len(), 1);
+
+        // And it goes by the schematic's adjacency
+        let input = parse_input("1.\n.*").unwrap();
+        assert_eq!(machines(&input, false).machines.len(), 1);
+        let input = input.with_adjacency("4".parse().unwrap());
+        assert_eq!(machines(&input, false).isolated.len(), 1);
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        // The biggest numbers there are, crowded round a symbol, so that both the sum and the
+        // gear ratios overflow anything narrower than an Answer
+        let biggest = "4294967295.4294967295\n..........*4294967295";
+        check_solver(
+            fixtures,
+            &[EXAMPLE_INPUT_DATA, biggest],
+            parse_input,
+            |parsed| {
+                let _ = part1(parsed);
+                let _ = part2(parsed);
+                for rule in ["*:3+:product", "*:1+:sum"] {
+                    let _ = gear_total(parsed, &rule.parse().unwrap());
+                }
+            },
+        );
     }
 }
@@ -0,0 +1,292 @@
+//! The parsed schematic, stored one of two ways. The dense grid has a cell for every character,
+//! which is quick to look things up in but gets huge for big schematics that are mostly dots.
+//! The sparse one only keeps the numbers and a hash of where the symbols are, and finds
+//! whatever's at a position from those instead.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use common::fuzz::Rng;
+
+use crate::adjacency::Adjacency;
+
+/// A number in the schematic, along with exactly where it was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberToken {
+    pub row: usize,
+    /// The column of the first digit
+    pub start_col: usize,
+    /// One past the column of the last digit, so leading zeros take up room too
+    pub end_col: usize,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    Nothing,
+    Symbol(char),
+    /// Index into the schematic's number tokens
+    Number(usize),
+}
+
+/// Which way to store the schematic. Both answer every question the same, they just trade
+/// memory for lookup speed differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// A component for every cell
+    #[default]
+    Dense,
+    /// Just the numbers and symbols, for when the grid is too big to keep whole
+    Sparse,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cells {
+    Dense(Vec<Vec<Component>>),
+    /// Symbols by their (x, y) position. Numbers are found by searching the tokens.
+    Sparse(HashMap<(usize, usize), char, BuildHasherDefault<PositionHasher>>),
+}
+
+/// Every cell around every number gets looked up in the sparse backend's symbols, and positions
+/// don't need SipHash's protection from deliberately colliding keys, so a multiply will do
+#[derive(Debug, Default)]
+struct PositionHasher(u64);
+
+impl Hasher for PositionHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+    cells: Cells,
+    numbers: Vec<NumberToken>,
+    n_rows: usize,
+    n_cols: usize,
+    adjacency: Adjacency,
+}
+
+impl Schematic {
+    /// Changes what counts as touching, for finding both part numbers and gears
+    pub fn with_adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    /// Every cell touching the number, not counting the number itself. Returns pairs of the
+    /// form (x, y), all of them inside the schematic.
+    pub fn neighbours(&self, token: &NumberToken) -> impl Iterator<Item = (usize, usize)> {
+        self.adjacency.around(
+            token.start_col..token.end_col,
+            token.row,
+            (self.n_cols, self.n_rows),
+        )
+    }
+
+    /// Every cell touching (x, y), as pairs of the form (x, y)
+    pub fn cell_neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.adjacency
+            .around(x..x + 1, y, (self.n_cols, self.n_rows))
+    }
+
+    /// What's at (x, y), which is nothing if it's off the edge
+    pub fn get(&self, x: usize, y: usize) -> Component {
+        match &self.cells {
+            Cells::Dense(grid) => grid
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(Component::Nothing),
+            Cells::Sparse(symbols) => {
+                if let Some(&symbol) = symbols.get(&(x, y)) {
+                    return Component::Symbol(symbol);
+                }
+                // The tokens are in reading order, so the only one that could cover (x, y) is
+                // the last one starting at or before it
+                let after = self
+                    .numbers
+                    .partition_point(|t| (t.row, t.start_col) <= (y, x));
+                match after.checked_sub(1).map(|id| (id, &self.numbers[id])) {
+                    Some((id, token)) if token.row == y && x < token.end_col => {
+                        Component::Number(id)
+                    }
+                    _ => Component::Nothing,
+                }
+            }
+        }
+    }
+
+    /// The symbol at (x, y), if there is one
+    pub fn symbol_at(&self, x: usize, y: usize) -> Option<char> {
+        match &self.cells {
+            Cells::Sparse(symbols) => symbols.get(&(x, y)).copied(),
+            Cells::Dense(_) => match self.get(x, y) {
+                Component::Symbol(symbol) => Some(symbol),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    pub fn n_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    /// Every number, in reading order
+    pub fn numbers(&self) -> &[NumberToken] {
+        &self.numbers
+    }
+
+    /// Every symbol alongside its (x, y) position, in reading order
+    pub fn symbols(&self) -> Vec<((usize, usize), char)> {
+        match &self.cells {
+            Cells::Dense(grid) => grid
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| {
+                    row.iter()
+                        .enumerate()
+                        .filter_map(move |(x, cell)| match cell {
+                            Component::Symbol(symbol) => Some(((x, y), *symbol)),
+                            _ => None,
+                        })
+                })
+                .collect(),
+            Cells::Sparse(symbols) => {
+                let mut symbols: Vec<_> = symbols.iter().map(|(&pos, &c)| (pos, c)).collect();
+                symbols.sort_by_key(|&((x, y), _)| (y, x));
+                symbols
+            }
+        }
+    }
+
+    /// Whether the number is touching any symbols
+    pub fn is_part(&self, token: &NumberToken) -> bool {
+        self.neighbours(token)
+            .any(|(x, y)| self.symbol_at(x, y).is_some())
+    }
+}
+
+pub fn parse_input(input: &str) -> Result<Schematic, String> {
+    parse_input_with(input, Backend::default())
+}
+
+pub fn parse_input_with(input: &str, backend: Backend) -> Result<Schematic, String> {
+    let mut numbers = vec![];
+    let mut symbols = vec![];
+    let mut n_rows = 0;
+    let mut n_cols = None;
+    // A trailing blank line isn't a (very short) row of the schematic
+    for (y, line) in input.trim_end_matches(['\n', '\r']).lines().enumerate() {
+        // Columns count chars rather than bytes, so that symbols like '§' or '€' only take up
+        // the one column
+        let chars: Vec<char> = line.chars().collect();
+        match n_cols {
+            None => n_cols = Some(chars.len()),
+            Some(n) if n != chars.len() => {
+                return Err(format!(
+                    "Line {}: expected {n} columns like the first line, found {}",
+                    y + 1,
+                    chars.len()
+                ));
+            }
+            Some(_) => {}
+        }
+        n_rows += 1;
+        let mut x = 0;
+        while x < chars.len() {
+            let chr = chars[x];
+            if chr.is_ascii_digit() {
+                // For parsing numbers
+                let number_length = chars[x..].iter().take_while(|c| c.is_ascii_digit()).count();
+                let digits: String = chars[x..x + number_length].iter().collect();
+                let value = digits
+                    .parse()
+                    .map_err(|_| format!("Line {}: number too big at column {}", y + 1, x + 1))?;
+                numbers.push(NumberToken {
+                    row: y,
+                    start_col: x,
+                    end_col: x + number_length,
+                    value,
+                });
+                x += number_length;
+                continue;
+            } else if chr != '.' {
+                // All else apart from nothingness are symbols, whatever language they're from
+                symbols.push(((x, y), chr));
+            }
+            x += 1;
+        }
+    }
+
+    let n_cols = n_cols.unwrap_or(0);
+    let cells = match backend {
+        Backend::Dense => {
+            let mut grid = vec![vec![Component::Nothing; n_cols]; n_rows];
+            for (id, token) in numbers.iter().enumerate() {
+                grid[token.row][token.start_col..token.end_col].fill(Component::Number(id));
+            }
+            for ((x, y), symbol) in symbols {
+                grid[y][x] = Component::Symbol(symbol);
+            }
+            Cells::Dense(grid)
+        }
+        Backend::Sparse => Cells::Sparse(symbols.into_iter().collect()),
+    };
+    Ok(Schematic {
+        cells,
+        numbers,
+        n_rows,
+        n_cols,
+        adjacency: Adjacency::default(),
+    })
+}
+
+/// A square schematic that's mostly dots, with the odd number or symbol scattered about, for
+/// benchmarking the backends against each other
+pub fn generate_schematic(size: usize, seed: u64) -> String {
+    const SYMBOLS: &[char] = &['*', '#', '+', '$', '/', '@', '=', '%', '-', '&'];
+    let mut rng = Rng::new(seed);
+    let mut input = String::with_capacity((size + 1) * size);
+    for _ in 0..size {
+        let mut x = 0;
+        while x < size {
+            let roll = rng.below(1000);
+            if roll < 10 {
+                // Numbers are kept apart by a dot, or they'd run together into one big one
+                let len = (1 + rng.below(3)).min(size - x);
+                for _ in 0..len {
+                    input.push((b'0' + rng.below(10) as u8) as char);
+                }
+                x += len;
+                if x < size {
+                    input.push('.');
+                    x += 1;
+                }
+                continue;
+            }
+            input.push(if roll < 15 { rng.pick(SYMBOLS) } else { '.' });
+            x += 1;
+        }
+        input.push('\n');
+    }
+    input
+}
@@ -1,32 +1,138 @@
+use common::answer::{Answer, AnswerError};
+use common::perf::{self, CountingAlloc, Recorder};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
+    if let Some(iterations) = perf::bench_iterations() {
+        bench(iterations);
+        return;
+    }
+
+    if let Some(path) = std::env::args().nth(1) {
+        // Inputs too big to comfortably fit in memory get streamed straight from disk instead
+        let open = || BufReader::new(File::open(&path).expect("Couldn't open the input file"));
+        println!("Day 1: {}", part1_from_reader(open()).unwrap());
+        println!("Day 2: {}", part2_from_reader(open()).unwrap());
+        return;
+    }
+
     let raw_input = include_str!("../input");
-    let parsed_input = parse_input(raw_input);
-    println!("Day 1: {}", part1(&parsed_input));
-    println!("Day 2: {}", part2(&parsed_input));
+    let parsed_input = parse_input(raw_input).expect("Couldn't parse the input");
+    println!("Day 1: {}", part1(&parsed_input).unwrap());
+    println!("Day 2: {}", part2(&parsed_input).unwrap());
 }
 
-fn part1(cards: &[Scratchcard]) -> u32 {
-    let mut total = 0;
+fn bench(iterations: usize) {
+    let parsed_input = parse_input(include_str!("../input")).expect("Couldn't parse the input");
+    let mut recorder = Recorder::new(4);
+    recorder.bench(1, iterations, || part1(&parsed_input));
+    recorder.bench(2, iterations, || part2(&parsed_input));
+    recorder
+        .save()
+        .expect("Couldn't save the benchmark results");
+}
+
+fn part1(cards: &[Scratchcard]) -> Result<Answer, AnswerError> {
+    let mut total = Answer::zero();
     for card in cards {
-        let winners = card_winners(card);
-        if winners > 0 {
-            total += 2_u32.pow(winners - 1);
-        }
+        total = total.checked_add(card_points(card)?)?;
+    }
+    Ok(total)
+}
+
+fn part2(cards: &[Scratchcard]) -> Result<Answer, AnswerError> {
+    let mut copies = CopyTracker::default();
+    for card in cards {
+        copies.add_card(card_winners(card))?;
     }
+    Ok(copies.total().clone())
+}
+
+/// Same as `part1`, but parses and scores one card at a time straight from the reader
+fn part1_from_reader(reader: impl BufRead) -> io::Result<Answer> {
+    let mut total = Answer::zero();
+    for card in cards_from_reader(reader) {
+        total = total.checked_add(card_points(&card?)?)?;
+    }
+    Ok(total)
+}
+
+/// Same as `part2`, but parses and scores one card at a time straight from the reader
+fn part2_from_reader(reader: impl BufRead) -> io::Result<Answer> {
+    let mut copies = CopyTracker::default();
+    for card in cards_from_reader(reader) {
+        copies.add_card(card_winners(&card?))?;
+    }
+    Ok(copies.total().clone())
+}
+
+fn cards_from_reader(reader: impl BufRead) -> impl Iterator<Item = io::Result<Scratchcard>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.as_ref().is_ok_and(|l| l.trim().is_empty()))
+        .map(|(i, line)| {
+            parse_line(&line?).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {e}", i + 1))
+            })
+        })
+}
+
+fn card_points(card: &Scratchcard) -> Result<Answer, AnswerError> {
+    let winners = card_winners(card);
+    if winners > 0 {
+        // A card with lots of numbers can easily be worth more than a u32 can hold
+        Answer::from(2).checked_pow(winners - 1)
+    } else {
+        Ok(Answer::zero())
+    }
+}
 
-    total
+/// Keeps count of how many copies of each card we end up with.
+/// A card can only ever hand out copies of the next few cards after it, so only the counts for
+/// those upcoming cards need remembering, rather than one count for every card in the pile.
+struct CopyTracker {
+    /// Extra copies already won of the upcoming cards, starting with the very next card
+    upcoming: VecDeque<Answer>,
+    total: Answer,
 }
+impl CopyTracker {
+    fn add_card(&mut self, winners: u32) -> Result<(), AnswerError> {
+        // The number of copies roughly doubles with every winning card, so this can get big fast
+        let copies = self
+            .upcoming
+            .pop_front()
+            .unwrap_or_else(Answer::zero)
+            .checked_add(1)?;
+        self.total = self.total.checked_add(copies.clone())?;
 
-fn part2(cards: &[Scratchcard]) -> u32 {
-    let mut card_counts: Vec<usize> = vec![1; cards.len()];
-    for (i, card) in cards.iter().enumerate() {
-        let winners = card_winners(card) as usize;
-        for j in (i + 1)..(i + 1 + winners) {
-            card_counts[j] += card_counts[i];
+        let winners = winners as usize;
+        if self.upcoming.len() < winners {
+            self.upcoming.resize(winners, Answer::zero());
         }
+        for count in self.upcoming.iter_mut().take(winners) {
+            *count = count.checked_add(copies.clone())?;
+        }
+        Ok(())
+    }
+
+    fn total(&self) -> &Answer {
+        &self.total
     }
+}
 
-    card_counts.into_iter().sum::<usize>() as u32
+impl Default for CopyTracker {
+    fn default() -> Self {
+        Self {
+            upcoming: VecDeque::new(),
+            total: Answer::zero(),
+        }
+    }
 }
 
 fn card_winners(card: &Scratchcard) -> u32 {
@@ -36,22 +142,31 @@ fn card_winners(card: &Scratchcard) -> u32 {
         .count() as u32
 }
 
-fn parse_input(input: &str) -> Vec<Scratchcard> {
-    let mut cards = vec![];
-    for (present, winning) in input.lines().map(|s| {
-        s.split_once(':')
-            .and_then(|(_, s)| s.split_once('|'))
-            .unwrap()
-    }) {
-        let [present, winning]: [Vec<u8>; 2] = [present, winning].map(|s| {
-            s.split_ascii_whitespace()
-                .filter_map(|s| s.parse::<u8>().ok())
-                .collect()
-        });
-        cards.push(Scratchcard { present, winning });
-    }
-    cards
+fn parse_input(input: &str) -> Result<Vec<Scratchcard>, String> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(line).map_err(|e| format!("Line {}: {e}", i + 1)))
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Scratchcard, String> {
+    let (present, winning) = line
+        .split_once(':')
+        .and_then(|(_, s)| s.split_once('|'))
+        .ok_or("Each line should be of the form \"Card n: ... | ...\"")?;
+    let parse_numbers = |s: &str| -> Result<Vec<u8>, String> {
+        s.split_ascii_whitespace()
+            .map(|n| n.parse::<u8>().map_err(|_| format!("Invalid number {n:?}")))
+            .collect()
+    };
+    Ok(Scratchcard {
+        present: parse_numbers(present)?,
+        winning: parse_numbers(winning)?,
+    })
 }
+
 struct Scratchcard {
     present: Vec<u8>,
     winning: Vec<u8>,
@@ -59,7 +174,12 @@ struct Scratchcard {
 
 #[cfg(test)]
 mod day4_tests {
-    use crate::{parse_input, part1, part2};
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
+
+    use crate::{parse_input, part1, part1_from_reader, part2, part2_from_reader};
 
     const EXAMPLE_INPUT: &str = "\
 Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
@@ -74,12 +194,51 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn test_part1() {
-        let parsed_input = parse_input(EXAMPLE_INPUT);
-        assert_eq!(part1(&parsed_input), EXPECTED_PART_1);
+        let parsed_input = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part1(&parsed_input), Ok(Answer::from(EXPECTED_PART_1)));
     }
     #[test]
     fn test_part2() {
-        let parsed_input = parse_input(EXAMPLE_INPUT);
-        assert_eq!(part2(&parsed_input), EXPECTED_PART_2);
+        let parsed_input = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part2(&parsed_input), Ok(Answer::from(EXPECTED_PART_2)));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let reader = || EXAMPLE_INPUT.as_bytes();
+        assert_eq!(
+            part1_from_reader(reader()).unwrap(),
+            Answer::from(EXPECTED_PART_1)
+        );
+        assert_eq!(
+            part2_from_reader(reader()).unwrap(),
+            Answer::from(EXPECTED_PART_2)
+        );
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        check_solver(fixtures, &[EXAMPLE_INPUT], parse_input, |parsed| {
+            let _ = part1(parsed);
+            let _ = part2(parsed);
+        });
+    }
+
+    #[test]
+    fn test_big_answers_dont_wrap() {
+        // 40 winning numbers is worth 2^39 points, which is far too many for a u32
+        let numbers: Vec<String> = (1..=40).map(|n| n.to_string()).collect();
+        let card = format!("Card 1: {0} | {0}", numbers.join(" "));
+        let parsed_input = parse_input(&card).unwrap();
+        assert_eq!(part1(&parsed_input), Ok(Answer::from(1_u64 << 39)));
+
+        // Every card wins a copy of the next 40 cards, so the copies snowball
+        let pile = vec![card.as_str(); 200].join("\n");
+        let parsed_input = parse_input(&pile).unwrap();
+        assert!(matches!(part2(&parsed_input), Ok(Answer::Big(_))));
     }
 }
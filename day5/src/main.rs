@@ -1,48 +1,82 @@
+use common::answer::Answer;
+use common::perf::{self, CountingAlloc, Recorder};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
     let raw_input = include_str!("../input");
-    let parsed_input = parse_input(raw_input);
+    let parsed_input = parse_input(raw_input).expect("Couldn't parse the input");
+
+    if let Some(iterations) = perf::bench_iterations() {
+        let mut recorder = Recorder::new(5);
+        recorder.bench(1, iterations, || part1(&parsed_input));
+        recorder.bench(2, iterations, || part2(&parsed_input));
+        recorder
+            .save()
+            .expect("Couldn't save the benchmark results");
+        return;
+    }
 
     prettyprint_almanac(&parsed_input);
 
-    println!("Part 1: {}", part1(&parsed_input));
-    println!("Part 2: {}", part2(&parsed_input));
+    println!(
+        "Part 1: {}",
+        part1(&parsed_input).expect("There aren't any seeds")
+    );
+    println!(
+        "Part 2: {}",
+        part2(&parsed_input).expect("There aren't any seeds")
+    );
 }
 
-fn lowest_location<'a>(seeds: impl Iterator<Item = i64>, map_groups: &[MapGroup]) -> i64 {
-    let mut min_location = i64::MAX;
-
-    for seed in seeds {
-        let mut curr_item = seed;
-        for map_group in map_groups {
-            let next_item = map_group
-                .map_ranges
-                .iter()
-                .filter_map(|range| range.try_map(curr_item))
-                .next()
-                .unwrap_or(curr_item);
-
-            curr_item = next_item;
-            // item_type = &map_group.value_type;
-        }
-        if curr_item < min_location {
-            min_location = curr_item;
+/// The lowest location for any seed in `seeds`, each of which is a range of the form
+/// (start, end) with the end left out. This is worked out in i128, where nothing read as an i64
+/// can overflow. `None` if there aren't any seeds at all.
+fn lowest_location(seeds: Vec<(i128, i128)>, map_groups: &[MapGroup]) -> Option<i128> {
+    let mut ranges = seeds;
+    for map_group in map_groups {
+        // Each map range takes whichever parts of the ranges it covers and nobody earlier did
+        let mut mapped = vec![];
+        for map_range in &map_group.map_ranges {
+            let (src_start, src_end) = map_range.src();
+            let diff = map_range.diff();
+            let mut unmapped = vec![];
+            for (start, end) in ranges {
+                let (lo, hi) = (start.max(src_start), end.min(src_end));
+                if lo >= hi {
+                    unmapped.push((start, end));
+                    continue;
+                }
+                mapped.push((lo + diff, hi + diff));
+                unmapped.extend([(start, lo), (hi, end)].into_iter().filter(|(s, e)| s < e));
+            }
+            ranges = unmapped;
         }
+        // Anything no map range covered keeps its number
+        ranges.extend(mapped);
     }
-    min_location
+    ranges.into_iter().map(|(start, _)| start).min()
 }
 
-fn part1(almanac: &Almanac) -> i64 {
-    lowest_location(almanac.seeds.iter().map(|n| *n), &almanac.map_groups)
+fn part1(almanac: &Almanac) -> Option<Answer> {
+    let seeds = almanac.seeds.iter().map(|&n| (n as i128, n as i128 + 1));
+    lowest_location(seeds.collect(), &almanac.map_groups).map(Answer::from)
 }
 
-fn part2(almanac: &Almanac) -> i64 {
+fn part2(almanac: &Almanac) -> Option<Answer> {
     // Uh-oh, the seeds are actually ranges!!
-    // This is super-bruteforce, but in release mode completes in ~90s on my machine.
-    let real_seeds = almanac.seeds.chunks_exact(2).flat_map(|c| {
-        debug_assert!(c.len() == 2);
-        c[0]..(c[0] + c[1])
+    // There are far too many to go through one at a time, so whole ranges get mapped at once,
+    // split up wherever they only partly overlap a map range.
+    let seeds = almanac.seeds.chunks_exact(2).map(|c| {
+        let start = c[0] as i128;
+        (start, start + c[1] as i128)
     });
-    lowest_location(real_seeds, &almanac.map_groups)
+    lowest_location(
+        seeds.filter(|(start, end)| start < end).collect(),
+        &almanac.map_groups,
+    )
+    .map(Answer::from)
 }
 
 struct Almanac {
@@ -62,20 +96,14 @@ struct MapRange {
     length: i64,
 }
 impl MapRange {
-    #[inline]
-    fn contains_in_src(&self, val: i64) -> bool {
-        self.src_start <= val && val <= self.src_start + self.length
-    }
-    #[inline]
-    fn diff(&self) -> i64 {
-        self.dest_start - self.src_start
+    /// The source numbers this covers, as (start, end) with the end left out
+    fn src(&self) -> (i128, i128) {
+        let start = self.src_start as i128;
+        (start, start + self.length as i128)
     }
-    fn try_map(&self, val: i64) -> Option<i64> {
-        if self.contains_in_src(val) {
-            Some(val + self.diff())
-        } else {
-            None
-        }
+
+    fn diff(&self) -> i128 {
+        self.dest_start as i128 - self.src_start as i128
     }
 }
 
@@ -92,41 +120,62 @@ fn prettyprint_almanac(almanac: &Almanac) {
     }
 }
 
-fn parse_input(input: &str) -> Almanac {
+fn parse_input(input: &str) -> Result<Almanac, String> {
     let mut grouping_iter = input.split("\n\n");
+    // Which line of the input each grouping starts on, for error messages
+    let mut line_no = 1;
 
-    let seeds_str = grouping_iter.next().unwrap();
+    let seeds_str = grouping_iter.next().unwrap_or_default();
     let seeds: Vec<i64> = seeds_str
         .strip_prefix("seeds: ")
-        .unwrap()
+        .ok_or("Line 1: the input should start with \"seeds: \"")?
         .split_ascii_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
+        .map(|s| s.parse().map_err(|_| format!("Line 1: invalid seed {s:?}")))
+        .collect::<Result<_, _>>()?;
+    line_no += seeds_str.lines().count() + 1;
 
     let mut map_groups = vec![];
     for grouping in grouping_iter {
-        let (mapping_label, data) = grouping.split_once(" map:\n").unwrap();
+        let group_line_no = line_no;
+        line_no += grouping.lines().count().max(1) + 1;
+        if grouping.trim().is_empty() {
+            continue;
+        }
+
+        let (mapping_label, data) = grouping.split_once(" map:\n").ok_or_else(|| {
+            format!("Line {group_line_no}: expected a \"<from>-to-<to> map:\" label")
+        })?;
 
         // Parsing the "from" and "to" of the mapping
-        let (key_type, value_type) = mapping_label.split_once("-to-").unwrap();
+        let (key_type, value_type) = mapping_label
+            .split_once("-to-")
+            .ok_or_else(|| format!("Line {group_line_no}: invalid map label {mapping_label:?}"))?;
         let from_type = key_type.to_string();
         let to_type = value_type.to_string();
 
         let mut map_ranges = vec![];
-        for line in data.lines() {
-            let mut num_iter = line
+        for (i, line) in data.lines().enumerate() {
+            let line_error = |e: &str| format!("Line {}: {e}", group_line_no + 1 + i);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let nums = line
                 .split_ascii_whitespace()
-                .map(|n| n.parse::<i64>().unwrap());
-            match (num_iter.next(), num_iter.next(), num_iter.next()) {
+                .map(|n| {
+                    n.parse::<i64>()
+                        .map_err(|_| line_error(&format!("invalid number {n:?}")))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match nums[..] {
                 // Each line is of the form [destination start], [source start], [range length]
-                (Some(dest_start), Some(src_start), Some(length)) => {
+                [dest_start, src_start, length] => {
                     map_ranges.push(MapRange {
                         dest_start,
                         src_start,
                         length,
                     });
                 }
-                _ => unreachable!(),
+                _ => return Err(line_error("expected exactly 3 numbers")),
             }
         }
 
@@ -137,11 +186,16 @@ fn parse_input(input: &str) -> Almanac {
         });
     }
 
-    Almanac { seeds, map_groups }
+    Ok(Almanac { seeds, map_groups })
 }
 
 #[cfg(test)]
 mod day5_tests {
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
+
     use crate::{parse_input, part1, part2};
 
     // If testing, ensure that day5/ contains the example-input file!
@@ -152,12 +206,24 @@ mod day5_tests {
 
     #[test]
     fn test_part1() {
-        let almanac = parse_input(EXAMPLE_INPUT);
-        assert_eq!(part1(&almanac), EXPECTED_PART_1);
+        let almanac = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part1(&almanac), Some(Answer::from(EXPECTED_PART_1)));
     }
     #[test]
     fn test_part2() {
-        let almanac = parse_input(EXAMPLE_INPUT);
-        assert_eq!(part2(&almanac), EXPECTED_PART_2);
+        let almanac = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part2(&almanac), Some(Answer::from(EXPECTED_PART_2)));
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        check_solver(fixtures, &[EXAMPLE_INPUT], parse_input, |parsed| {
+            let _ = part1(parsed);
+            let _ = part2(parsed);
+        });
     }
 }
@@ -1,43 +1,66 @@
+use common::answer::{Answer, AnswerError};
+use common::perf::{self, CountingAlloc, Recorder};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
     let raw_input = include_str!("../input");
 
-    let p1_parsed_input = part1_parse(raw_input);
-    println!("Part 1: {}", part1(&p1_parsed_input));
+    if let Some(iterations) = perf::bench_iterations() {
+        let p1_parsed_input = part1_parse(raw_input).expect("Couldn't parse the input");
+        let p2_parsed_input = part2_parse(raw_input).expect("Couldn't parse the input");
+        let mut recorder = Recorder::new(6);
+        recorder.bench(1, iterations, || part1(&p1_parsed_input));
+        recorder.bench(2, iterations, || part2(&p2_parsed_input));
+        recorder
+            .save()
+            .expect("Couldn't save the benchmark results");
+        return;
+    }
+
+    let p1_parsed_input = part1_parse(raw_input).expect("Couldn't parse the input");
+    println!("Part 1: {}", part1(&p1_parsed_input).unwrap());
 
-    let p2_parsed_input = part2_parse(&raw_input);
+    let p2_parsed_input = part2_parse(raw_input).expect("Couldn't parse the input");
     println!("Part 2: {}", part2(&p2_parsed_input));
 }
 
-fn part1(races: &[Race]) -> i64 {
-    let mut total = 1;
+fn part1(races: &[Race]) -> Result<Answer, AnswerError> {
+    let mut total = Answer::one();
     for race in races {
-        let (root1, root2) = winning_hold_durations(race);
-        let range = root2 - root1 + 1;
-        total *= range;
+        total = total.checked_mul(ways_to_win(race))?;
     }
-    total
+    Ok(total)
 }
 
-fn part2(race: &Race) -> i64 {
-    let (root1, root2) = winning_hold_durations(race);
-    root2 - root1 + 1
+fn part2(race: &Race) -> u64 {
+    ways_to_win(race)
 }
 
-fn winning_hold_durations(race: &Race) -> (i64, i64) {
+fn ways_to_win(race: &Race) -> u64 {
     // For a given race of duration `duration`ms, holding the button for `hold`ms will result in
     // moving hold * (duration - hold) mm, AKA -hold^2 + duration*hold
-    // Our goal is to find the range of distances where that duration is > the current record,
-    // AKA -hold^2 + duration*hold > record,
-    // AKA -hold^2 + duration*hold - record > 0
-    // I see a quadratic equation!!!
-    quadratic(-1, race.duration, -race.record)
-}
-
-fn quadratic(a: i64, b: i64, c: i64) -> (i64, i64) {
-    let sqrt_discrimnant = (((b * b) - (4 * a * c)) as f64).sqrt();
-    let root1 = ((-b as f64) + sqrt_discrimnant) / (2 * a) as f64;
-    let root2 = ((-b as f64) - sqrt_discrimnant) / (2 * a) as f64;
-    (root1.floor() as i64 + 1, root2.ceil() as i64 - 1)
+    // Our goal is to find the range of distances where that duration is > the current record.
+    // That's symmetrical around holding for half the race, so it's every hold from the first
+    // one that wins up to the same distance from the other end.
+    // Solving the quadratic in floats loses precision once the numbers get big, so instead the
+    // first winning hold gets searched for, in i128 where the distances can't overflow.
+    let (duration, record) = (race.duration as i128, race.record as i128);
+    let distance = |hold: i128| hold * (duration - hold);
+    if duration < 0 || distance(duration / 2) <= record {
+        return 0;
+    }
+    let (mut lo, mut hi) = (0, duration / 2);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if distance(mid) > record {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    (duration - 2 * lo + 1) as u64
 }
 
 struct Race {
@@ -45,33 +68,103 @@ struct Race {
     record: i64,
 }
 
-fn part1_parse(input: &str) -> Vec<Race> {
+/// The "Time:" and "Distance:" lines, without their labels
+fn labelled_lines(input: &str) -> Result<[&str; 2], String> {
     let mut lines = input.lines();
-    let times_line = lines.next().and_then(|s| s.strip_prefix("Time:")).unwrap();
+    let times_line = lines
+        .next()
+        .and_then(|s| s.strip_prefix("Time:"))
+        .ok_or("Line 1 should start with \"Time:\"")?;
     let distances_line = lines
         .next()
         .and_then(|s| s.strip_prefix("Distance:"))
-        .unwrap();
-    let [times, distances] = [times_line, distances_line].map(|line| {
+        .ok_or("Line 2 should start with \"Distance:\"")?;
+    Ok([times_line, distances_line])
+}
+
+fn part1_parse(input: &str) -> Result<Vec<Race>, String> {
+    let [times, distances] = labelled_lines(input)?.map(|line| {
         line.split_ascii_whitespace()
-            .map(|s| s.parse::<i64>().unwrap())
+            .map(|s| {
+                s.parse::<i64>()
+                    .map_err(|_| format!("Invalid number {s:?}"))
+            })
+            .collect::<Result<Vec<_>, _>>()
     });
-    times
+    let (times, distances) = (times?, distances?);
+    if times.len() != distances.len() {
+        return Err(format!(
+            "There are {} times but {} distances",
+            times.len(),
+            distances.len()
+        ));
+    }
+    Ok(times
+        .into_iter()
         .zip(distances)
         .map(|(time, distance)| Race {
             duration: time,
             record: distance,
         })
-        .collect()
+        .collect())
 }
 
-fn part2_parse(input: &str) -> Race {
-    let mut lines = input.lines();
-    let [time, dist]: [String; 2] = [lines.next().unwrap(), lines.next().unwrap()]
-        .map(|line| line.chars().filter(char::is_ascii_digit).collect());
+fn part2_parse(input: &str) -> Result<Race, String> {
+    // The kerning's bad, so every digit on the line is part of one big number
+    let [time, dist]: [String; 2] =
+        labelled_lines(input)?.map(|line| line.chars().filter(char::is_ascii_digit).collect());
+    let parse = |n: String| {
+        n.parse()
+            .map_err(|_| format!("Invalid number {n:?} (empty or too big)"))
+    };
+
+    Ok(Race {
+        duration: parse(time)?,
+        record: parse(dist)?,
+    })
+}
+
+#[cfg(test)]
+mod day6_tests {
+    use std::path::Path;
+
+    use common::fuzz::check_solver;
+
+    use common::answer::Answer;
+
+    use crate::{part1, part1_parse, part2, part2_parse};
+
+    const EXAMPLE_INPUT: &str = "Time:      7  15   30\nDistance:  9  40  200\n";
+
+    #[test]
+    fn test_part1() {
+        let races = part1_parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part1(&races).unwrap(), Answer::from(288));
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(&part2_parse(EXAMPLE_INPUT).unwrap()), 71503);
+    }
 
-    Race {
-        duration: time.parse().unwrap(),
-        record: dist.parse().unwrap(),
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz-fixtures"));
+        check_solver(
+            &fixtures.join("part1_parse"),
+            &[EXAMPLE_INPUT],
+            part1_parse,
+            |races| {
+                let _ = part1(races);
+            },
+        );
+        check_solver(
+            &fixtures.join("part2_parse"),
+            &[EXAMPLE_INPUT],
+            part2_parse,
+            |race| {
+                let _ = part2(race);
+            },
+        );
     }
 }
@@ -1,41 +1,180 @@
+use common::answer::{Answer, AnswerError};
+use common::perf::{self, CountingAlloc, Recorder};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
+    if let Some(iterations) = perf::bench_iterations() {
+        bench(iterations);
+        return;
+    }
+
+    if let Some(path) = std::env::args().nth(1) {
+        // Inputs too big to comfortably fit in memory get streamed straight from disk instead
+        let open = || BufReader::new(File::open(&path).expect("Couldn't open the input file"));
+        println!(
+            "Part 1: {}",
+            total_winnings_from_reader(open(), false).unwrap()
+        );
+        println!(
+            "Part 2: {}",
+            total_winnings_from_reader(open(), true).unwrap()
+        );
+        return;
+    }
+
     let raw_input = include_str!("../input");
-    let parsed_input_pt1 = parse_input(raw_input, false);
-    let parsed_input_pt2 = parse_input(raw_input, true);
+    let parsed_input_pt1 = parse_input(raw_input, false).expect("Couldn't parse the input");
+    let parsed_input_pt2 = parse_input(raw_input, true).expect("Couldn't parse the input");
+
+    println!("Part 1: {}", total_winnings(&parsed_input_pt1).unwrap());
+    println!("Part 2: {}", total_winnings(&parsed_input_pt2).unwrap());
+}
+
+fn bench(iterations: usize) {
+    let raw_input = include_str!("../input");
+    let parsed_input_pt1 = parse_input(raw_input, false).expect("Couldn't parse the input");
+    let parsed_input_pt2 = parse_input(raw_input, true).expect("Couldn't parse the input");
+    let mut recorder = Recorder::new(7);
+    recorder.bench(1, iterations, || total_winnings(&parsed_input_pt1));
+    recorder.bench(2, iterations, || total_winnings(&parsed_input_pt2));
+    recorder
+        .save()
+        .expect("Couldn't save the benchmark results");
+}
+
+fn total_winnings(input: &[(Hand, u32)]) -> Result<Answer, AnswerError> {
+    let mut table = WinningsTable::new();
+    for (hand, bet) in input {
+        table.add(hand, *bet)?;
+    }
+    table.total()
+}
+
+/// Same as `total_winnings`, but parses one hand at a time straight from the reader
+fn total_winnings_from_reader(reader: impl BufRead, for_part2: bool) -> io::Result<Answer> {
+    let card_parsing_fn = card_parsing_fn(for_part2);
+    let mut table = WinningsTable::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (hand, bet) = parse_line(&line, card_parsing_fn).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {e}", i + 1))
+        })?;
+        table.add(&hand, bet)?;
+    }
+    Ok(table.total()?)
+}
 
-    println!("Part 1: {}", total_winnings(&parsed_input_pt1));
-    println!("Part 1: {}", total_winnings(&parsed_input_pt2));
+/// Tallies up the bets placed on each distinct hand, so that the total winnings can be worked
+/// out without ever having to sort (or even hold onto) the whole list of hands.
+///
+/// Each hand is keyed by its type, then its cards read as the digits of a base-14 number. That
+/// makes walking the keys in order the same as walking the hands from weakest to strongest, and
+/// the table only ever grows as big as the number of different hands actually seen.
+struct WinningsTable {
+    slots: BTreeMap<(u8, u32), Slot>,
 }
 
-fn total_winnings(input: &[(Hand, i32)]) -> i32 {
-    let mut hand_bet_pairs = input.to_vec();
-    let mut total = 0;
-    hand_bet_pairs.sort_by(|(c1, _), (c2, _)| c1.cmp(c2));
-    for (i, (_, bet)) in hand_bet_pairs.iter().enumerate() {
-        total += (1 + i as i32) * *bet;
+#[derive(Clone, Copy, Default)]
+struct Slot {
+    count: u64,
+    bets: u64,
+    /// Identical hands are ranked in the order they were seen in, so this is the sum of each
+    /// bet multiplied by how many identical hands came before it
+    tiebroken_bets: u64,
+}
+
+impl WinningsTable {
+    fn new() -> Self {
+        Self {
+            slots: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, hand: &Hand, bet: u32) -> Result<(), AnswerError> {
+        let cards = hand
+            .0
+            .iter()
+            .fold(0, |acc, card| acc * Card::amount() as u32 + *card as u32);
+        let slot = self
+            .slots
+            .entry((hand.hand_type() as u8, cards))
+            .or_default();
+        let overflow = || AnswerError::Overflow("the bets on one hand".to_string());
+        let bet = bet as u64;
+        slot.tiebroken_bets = slot
+            .count
+            .checked_mul(bet)
+            .and_then(|b| b.checked_add(slot.tiebroken_bets))
+            .ok_or_else(overflow)?;
+        slot.bets = slot.bets.checked_add(bet).ok_or_else(overflow)?;
+        slot.count += 1;
+        Ok(())
+    }
+
+    fn total(&self) -> Result<Answer, AnswerError> {
+        let mut total = Answer::zero();
+        // How many hands are ranked below the current one
+        let mut ranked_below = 0;
+        for slot in self.slots.values() {
+            let winnings = Answer::from(1 + ranked_below)
+                .checked_mul(slot.bets)?
+                .checked_add(slot.tiebroken_bets)?;
+            total = total.checked_add(winnings)?;
+            ranked_below += slot.count;
+        }
+        Ok(total)
     }
-    total
 }
 
-fn parse_input(input: &str, for_part2: bool) -> Vec<(Hand, i32)> {
-    let card_parsing_fn = if for_part2 {
+fn card_parsing_fn(for_part2: bool) -> fn(char) -> Option<Card> {
+    if for_part2 {
         Card::from_char_pt2
     } else {
         Card::from_char
-    };
+    }
+}
 
-    let mut pairs = vec![];
-    for (hand_s, bet_s) in input.lines().map(|line| line.split_once(' ').unwrap()) {
-        // Stable rust doesn't have Iter::next_chunk yet :(
-        let hand_vec: Vec<Card> = hand_s.chars().map(card_parsing_fn).collect();
-        let mut hand = [Card::Ace; 5];
-        hand.copy_from_slice(&hand_vec[0..5]);
+fn parse_input(input: &str, for_part2: bool) -> Result<Vec<(Hand, u32)>, String> {
+    let card_parsing_fn = card_parsing_fn(for_part2);
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            parse_line(line, card_parsing_fn).map_err(|e| format!("Line {}: {e}", i + 1))
+        })
+        .collect()
+}
 
-        let bet: i32 = bet_s.parse().unwrap();
-        pairs.push((Hand(hand), bet));
-    }
+fn parse_line(
+    line: &str,
+    card_parsing_fn: fn(char) -> Option<Card>,
+) -> Result<(Hand, u32), String> {
+    let (hand_s, bet_s) = line
+        .split_once(' ')
+        .ok_or("Each line should be a hand and a bet, separated by a space")?;
+    // Stable rust doesn't have Iter::next_chunk yet :(
+    let hand_vec: Vec<Card> = hand_s
+        .chars()
+        .map(|c| card_parsing_fn(c).ok_or_else(|| format!("Invalid card {c:?}")))
+        .collect::<Result<_, _>>()?;
+    let hand: [Card; 5] = hand_vec
+        .try_into()
+        .map_err(|v: Vec<Card>| format!("A hand should have 5 cards, not {}", v.len()))?;
 
-    pairs
+    let bet: u32 = bet_s
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid bet {bet_s:?}"))?;
+    Ok((Hand(hand), bet))
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -59,8 +198,8 @@ impl Card {
     const fn amount() -> usize {
         14
     }
-    fn from_char(c: char) -> Self {
-        match c {
+    fn from_char(c: char) -> Option<Self> {
+        Some(match c {
             'A' => Self::Ace,
             'K' => Self::King,
             'Q' => Self::Queen,
@@ -74,15 +213,15 @@ impl Card {
             '4' => Self::Four,
             '3' => Self::Three,
             '2' => Self::Two,
-            _ => panic!("Invalid cast to Card: {c}"),
-        }
+            _ => return None,
+        })
     }
     /// Nearly identical to `from_char`, except Js get mapped to Joker instead of Jack
-    fn from_char_pt2(c: char) -> Self {
-        match Self::from_char(c) {
+    fn from_char_pt2(c: char) -> Option<Self> {
+        Self::from_char(c).map(|card| match card {
             Self::Jack => Self::Joker,
             other => other,
-        }
+        })
     }
 }
 
@@ -175,3 +314,84 @@ impl PartialEq for Hand {
     }
 }
 impl Eq for Hand {}
+
+#[cfg(test)]
+mod day7_tests {
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
+
+    use crate::{parse_input, total_winnings, total_winnings_from_reader};
+
+    const EXAMPLE_INPUT: &str = "\
+32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    const EXPECTED_PART_1: u32 = 6440;
+    const EXPECTED_PART_2: u32 = 5905;
+
+    #[test]
+    fn test_total_winnings() {
+        assert_eq!(
+            total_winnings(&parse_input(EXAMPLE_INPUT, false).unwrap()),
+            Ok(Answer::from(EXPECTED_PART_1))
+        );
+        assert_eq!(
+            total_winnings(&parse_input(EXAMPLE_INPUT, true).unwrap()),
+            Ok(Answer::from(EXPECTED_PART_2))
+        );
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let reader = || EXAMPLE_INPUT.as_bytes();
+        assert_eq!(
+            total_winnings_from_reader(reader(), false).unwrap(),
+            Answer::from(EXPECTED_PART_1)
+        );
+        assert_eq!(
+            total_winnings_from_reader(reader(), true).unwrap(),
+            Answer::from(EXPECTED_PART_2)
+        );
+    }
+
+    #[test]
+    fn test_identical_hands_ranked_in_input_order() {
+        // Both hands tie, so the stable sort ranks the 10 first, then the 100
+        let input = parse_input("AKQJT 10\nAKQJT 100\n2345Q 1", false).unwrap();
+        assert_eq!(
+            total_winnings(&input),
+            Ok(Answer::from(1 + 2 * 10 + 3 * 100))
+        );
+    }
+
+    #[test]
+    fn test_big_bets_dont_wrap() {
+        // Used to overflow an i32 straight away
+        let input = parse_input("AAAAA 4000000000\n22222 4000000000", false).unwrap();
+        assert_eq!(
+            total_winnings(&input),
+            Ok(Answer::from(4_000_000_000_u64 * 3))
+        );
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        // Both parts read the cards differently, so each gets its own go at the input
+        let parse_both = |s: &str| Ok::<_, String>((parse_input(s, false)?, parse_input(s, true)?));
+        check_solver(fixtures, &[EXAMPLE_INPUT], parse_both, |(part1, part2)| {
+            let _ = total_winnings(part1);
+            let _ = total_winnings(part2);
+        });
+        assert!(parse_input("AKQ 12\n", false).is_err());
+        assert!(total_winnings_from_reader("AKQJT 1\nAKQJT one".as_bytes(), false).is_err());
+    }
+}
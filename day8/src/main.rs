@@ -1,32 +1,53 @@
+use common::answer::Answer;
+use common::perf::{self, CountingAlloc, Recorder};
 use std::collections::HashMap;
 
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
     let raw_input = include_str!("../input");
 
-    let (directions, map) = parse_input(&raw_input);
+    let (directions, map) = parse_input(raw_input).expect("Couldn't parse the input");
+
+    if let Some(iterations) = perf::bench_iterations() {
+        let mut recorder = Recorder::new(8);
+        recorder.bench(1, iterations, || part1(&directions, &map));
+        recorder.bench(2, iterations, || part2(&directions, &map));
+        recorder
+            .save()
+            .expect("Couldn't save the benchmark results");
+        return;
+    }
 
-    println!("Part 1: {}", part1(&directions, &map));
-    println!("Part 2: {}", part2(&directions, &map));
+    println!("Part 1: {}", part1(&directions, &map).unwrap());
+    println!("Part 2: {}", part2(&directions, &map).unwrap());
 }
 
-fn part1(directions: &[Direction], map: &Network) -> i64 {
-    path_length(b"AAA", |loc| loc == b"ZZZ", directions, map)
+fn part1(directions: &[Direction], map: &Network) -> Result<Answer, String> {
+    path_length(b"AAA", |loc| loc == b"ZZZ", directions, map).map(Answer::from)
 }
 
-fn part2(directions: &[Direction], map: &Network) -> i64 {
+fn part2(directions: &[Direction], map: &Network) -> Result<Answer, String> {
     let starter_locations: Vec<&Location> = map.keys().filter(|k| k[2] == b'A').collect();
-    let mut lengths = vec![];
+    // The lowest common multiple of the lengths can outgrow any fixed-width integer, so instead
+    // of working it out directly, each length is divided by whatever it shares with the ones
+    // before it, and what's left of them all gets multiplied together
+    let mut factors = vec![];
     for loc in starter_locations {
-        lengths.push(path_length(loc, |loc| loc[2] == b'Z', directions, map));
+        let mut length = path_length(loc, |loc| loc[2] == b'Z', directions, map)?;
+        for &factor in &factors {
+            length /= gcd(length, factor);
+        }
+        factors.push(length);
     }
-    lengths.into_iter().fold(1, |acc, r| lcm(acc, r))
-}
-
-fn lcm(l: i64, r: i64) -> i64 {
-    l / gcd(l, r) * r
+    factors
+        .into_iter()
+        .try_fold(Answer::one(), |total, factor| total.checked_mul(factor))
+        .map_err(|e| e.to_string())
 }
 
-fn gcd(l: i64, r: i64) -> i64 {
+fn gcd(l: u64, r: u64) -> u64 {
     if r == 0 {
         l
     } else {
@@ -34,18 +55,33 @@ fn gcd(l: i64, r: i64) -> i64 {
     }
 }
 
+/// How many steps it takes to get from `start` to somewhere that meets the end condition.
+/// Fails if the directions lead to a node that isn't in the map, or round in circles forever.
 fn path_length(
     start: &Location,
     end_condition: fn(&Location) -> bool,
     directions: &[Direction],
     map: &Network,
-) -> i64 {
+) -> Result<u64, String> {
+    // Once every node has been reached at every point in the directions, it's all repeats
+    let give_up = directions.len() * map.len();
     let mut current_location: &Location = start;
     let mut dir_iter = directions.iter().cycle();
     let mut steps = 0;
     while !end_condition(current_location) {
+        if steps > give_up {
+            return Err(format!(
+                "There's no way to the end from {}",
+                String::from_utf8_lossy(start)
+            ));
+        }
         let direction = dir_iter.next().unwrap();
-        let node = map.get(current_location).unwrap();
+        let node = map.get(current_location).ok_or_else(|| {
+            format!(
+                "There's no node called {}",
+                String::from_utf8_lossy(current_location)
+            )
+        })?;
         current_location = match direction {
             Direction::Left => &node.0,
             Direction::Right => &node.1,
@@ -53,7 +89,7 @@ fn path_length(
         steps += 1;
     }
 
-    steps
+    Ok(steps as u64)
 }
 
 #[derive(Debug)]
@@ -64,29 +100,132 @@ enum Direction {
 type Location = [u8; 3];
 type Network = HashMap<Location, (Location, Location)>;
 
-fn parse_input(input: &str) -> (Vec<Direction>, Network) {
-    let (direction, nodes) = input.split_once("\n\n").unwrap();
+fn parse_input(input: &str) -> Result<(Vec<Direction>, Network), String> {
+    let (direction, nodes) = input
+        .split_once("\n\n")
+        .ok_or("The directions and nodes should be separated by a blank line")?;
 
-    let directions = direction
-        .bytes()
-        .map(|b| match b {
-            b'L' => Direction::Left,
-            b'R' => Direction::Right,
-            _ => unreachable!(),
+    let directions: Vec<Direction> = direction
+        .trim_end()
+        .chars()
+        .map(|c| match c {
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            _ => Err(format!("Line 1: invalid direction {c:?}")),
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
+    if directions.is_empty() {
+        return Err("Line 1: there should be at least one direction".to_string());
+    }
 
+    let first_node_line = direction.lines().count() + 2;
     let network = nodes
         .lines()
-        .map(|line| {
-            let bytes = line.as_bytes();
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let bytes = line.trim_end().as_bytes();
+            // Every line looks exactly like "AAA = (BBB, CCC)"
+            if bytes.len() != 16
+                || &bytes[3..7] != b" = ("
+                || &bytes[10..12] != b", "
+                || bytes[15] != b')'
+            {
+                return Err(format!(
+                    "Line {}: expected a node like \"AAA = (BBB, CCC)\", got {line:?}",
+                    first_node_line + i
+                ));
+            }
             // 0..3, 7..10, 12..15
             let [node_name, lhs, rhs] =
                 [0..3, 7..10, 12..15].map(|range| bytes[range].try_into().unwrap());
 
-            (node_name, (lhs, rhs))
+            Ok((node_name, (lhs, rhs)))
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
+
+    Ok((directions, network))
+}
+
+#[cfg(test)]
+mod day8_tests {
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
 
-    (directions, network)
+    use crate::{parse_input, part1, part2};
+
+    const EXAMPLE_INPUT: &str = "\
+LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)
+";
+
+    #[test]
+    fn test_part1() {
+        let (directions, map) = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(part1(&directions, &map), Ok(Answer::from(6)));
+    }
+
+    #[test]
+    fn test_part2() {
+        let (directions, map) = parse_input(
+            "\
+LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+",
+        )
+        .unwrap();
+        assert_eq!(part2(&directions, &map), Ok(Answer::from(6)));
+        // Lengths sharing factors only count them once
+        let (directions, map) = parse_input(
+            "\
+L
+
+11A = (11B, 11B)
+11B = (11C, 11C)
+11C = (11D, 11D)
+11D = (11Z, 11Z)
+11Z = (11Z, 11Z)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22Z, 22Z)
+",
+        )
+        .unwrap();
+        assert_eq!(part2(&directions, &map), Ok(Answer::from(4)));
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        check_solver(
+            fixtures,
+            &[EXAMPLE_INPUT],
+            parse_input,
+            |(directions, map)| {
+                let _ = part1(directions, map);
+                let _ = part2(directions, map);
+            },
+        );
+        // Parsing fine doesn't mean the nodes lead anywhere
+        let (directions, map) = parse_input("L\n\nAAA = (BBB, BBB)\n").unwrap();
+        assert!(part1(&directions, &map).is_err());
+        let (directions, map) = parse_input("LR\n\nAAA = (AAA, BBB)\nBBB = (AAA, ZZZ)\n").unwrap();
+        assert!(part1(&directions, &map).is_err());
+    }
 }
@@ -1,54 +1,202 @@
+use common::answer::{Answer, AnswerError};
+use common::perf::{self, CountingAlloc, Recorder};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
 fn main() {
+    if let Some(iterations) = perf::bench_iterations() {
+        bench(iterations);
+        return;
+    }
+
+    if let Some(path) = std::env::args().nth(1) {
+        // Inputs too big to comfortably fit in memory get streamed straight from disk instead
+        let open = || BufReader::new(File::open(&path).expect("Couldn't open the input file"));
+        println!(
+            "Part 1: {}",
+            predict_from_reader(open(), Part::One).unwrap()
+        );
+        println!(
+            "Part 2: {}",
+            predict_from_reader(open(), Part::Two).unwrap()
+        );
+        return;
+    }
+
     let raw_input = include_str!("../input");
-    let parsed_input = parse_input(raw_input);
+    let parsed_input = parse_input(raw_input).expect("Couldn't parse the input");
+
+    println!("Part 1: {}", predict(&parsed_input, Part::One).unwrap());
+    println!("Part 2: {}", predict(&parsed_input, Part::Two).unwrap());
+}
 
-    println!("Part 1: {}", predict(&parsed_input, Part::One));
-    println!("Part 2: {}", predict(&parsed_input, Part::Two));
+fn bench(iterations: usize) {
+    let parsed_input = parse_input(include_str!("../input")).expect("Couldn't parse the input");
+    let mut recorder = Recorder::new(9);
+    recorder.bench(1, iterations, || predict(&parsed_input, Part::One));
+    recorder.bench(2, iterations, || predict(&parsed_input, Part::Two));
+    recorder
+        .save()
+        .expect("Couldn't save the benchmark results");
 }
 
+#[derive(Clone, Copy)]
 enum Part {
     One,
     Two,
 }
 
-fn predict(input: &[Vec<i64>], part: Part) -> i64 {
-    let (diff_getter, diff_folder): (fn(&Vec<i64>) -> i64, fn(Vec<i64>) -> i64) = match part {
+fn predict(input: &[Vec<i64>], part: Part) -> Result<Answer, AnswerError> {
+    input
+        .iter()
+        .enumerate()
+        .try_fold(Answer::zero(), |total, (i, line)| {
+            total.checked_add(predict_line(line, part).ok_or_else(|| too_big(i + 1))?)
+        })
+}
+
+/// Same as `predict`, but parses one history at a time straight from the reader
+fn predict_from_reader(reader: impl BufRead, part: Part) -> io::Result<Answer> {
+    let mut total = Answer::zero();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let history = parse_line(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {e}", i + 1))
+        })?;
+        total = total.checked_add(predict_line(&history, part).ok_or_else(|| too_big(i + 1))?)?;
+    }
+    Ok(total)
+}
+
+fn too_big(line_no: usize) -> AnswerError {
+    AnswerError::Overflow(format!("the prediction on line {line_no}"))
+}
+
+/// Adds up the differences picked out of each derived sequence, or `None` on overflow
+type DiffFolder = fn(Vec<i128>) -> Option<i128>;
+
+/// The next (or previous) value in the history. Each round of differences can be up to twice
+/// as big as the last, so this is worked out in i128, and is `None` if even that overflows.
+fn predict_line(line: &[i64], part: Part) -> Option<i128> {
+    let (diff_getter, diff_folder): (fn(&[i128]) -> i128, DiffFolder) = match part {
         Part::One => (
             |v| *v.last().unwrap(),
-            |v| v.into_iter().fold(0, |acc, el| acc + el),
+            |v| v.into_iter().try_fold(0i128, |acc, el| acc.checked_add(el)),
         ),
         Part::Two => (
             |v| *v.first().unwrap(),
-            |v| -v.into_iter().rfold(0, |acc, el| el - acc),
+            |v| {
+                v.into_iter()
+                    .try_rfold(0i128, |acc, el| el.checked_sub(acc))?
+                    .checked_neg()
+            },
         ),
     };
 
-    let mut total = 0;
-    for line in input.iter() {
-        let mut diffs = vec![];
-        let mut derived = derive(&line);
-        while derived.iter().any(|n| n != &0) {
-            diffs.push(diff_getter(&derived));
-            derived = derive(&derived);
-        }
-        // let prediction = *line.first().unwrap() - diffs.into_iter().rfold(0, |acc, el| el - acc);
-        let prediction = diff_getter(&line) + diff_folder(diffs);
-        total += prediction;
+    let line: Vec<i128> = line.iter().map(|&n| n as i128).collect();
+    let mut diffs = vec![];
+    let mut derived = derive(&line)?;
+    while derived.iter().any(|n| n != &0) {
+        diffs.push(diff_getter(&derived));
+        derived = derive(&derived)?;
     }
-    total
+    // let prediction = *line.first().unwrap() - diffs.into_iter().rfold(0, |acc, el| el - acc);
+    diff_getter(&line).checked_add(diff_folder(diffs)?)
 }
 
-fn derive(history: &[i64]) -> Vec<i64> {
-    history.windows(2).map(|w| w[1] - w[0]).collect()
+fn derive(history: &[i128]) -> Option<Vec<i128>> {
+    history.windows(2).map(|w| w[1].checked_sub(w[0])).collect()
 }
 
-fn parse_input(input: &str) -> Vec<Vec<i64>> {
+fn parse_input(input: &str) -> Result<Vec<Vec<i64>>, String> {
     input
         .lines()
-        .map(|line| {
-            line.split_ascii_whitespace()
-                .map(|s| s.parse().unwrap())
-                .collect()
-        })
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(line).map_err(|e| format!("Line {}: {e}", i + 1)))
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Vec<i64>, String> {
+    line.split_ascii_whitespace()
+        .map(|s| s.parse().map_err(|_| format!("Invalid number {s:?}")))
         .collect()
 }
+
+#[cfg(test)]
+mod day9_tests {
+    use std::path::Path;
+
+    use common::answer::Answer;
+    use common::fuzz::check_solver;
+
+    use crate::{parse_input, predict, predict_from_reader, Part};
+
+    const EXAMPLE_INPUT: &str = "\
+0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45";
+
+    const EXPECTED_PART_1: i64 = 114;
+    const EXPECTED_PART_2: i64 = 2;
+
+    #[test]
+    fn test_predict() {
+        let parsed_input = parse_input(EXAMPLE_INPUT).unwrap();
+        assert_eq!(
+            predict(&parsed_input, Part::One),
+            Ok(Answer::from(EXPECTED_PART_1))
+        );
+        assert_eq!(
+            predict(&parsed_input, Part::Two),
+            Ok(Answer::from(EXPECTED_PART_2))
+        );
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let reader = || EXAMPLE_INPUT.as_bytes();
+        assert_eq!(
+            predict_from_reader(reader(), Part::One).unwrap(),
+            Answer::from(EXPECTED_PART_1)
+        );
+        assert_eq!(
+            predict_from_reader(reader(), Part::Two).unwrap(),
+            Answer::from(EXPECTED_PART_2)
+        );
+    }
+
+    #[test]
+    fn test_big_histories() {
+        // Both the differences and the prediction go past an i64
+        let parsed_input = parse_input("-9223372036854775808 9223372036854775807 0").unwrap();
+        let prediction = i64::MIN as i128 - 3 * i64::MAX as i128;
+        assert_eq!(
+            predict(&parsed_input, Part::One),
+            Ok(Answer::from(prediction))
+        );
+        // Each round of differences doubles, so enough of them go past an i128 too
+        let line = ["-9223372036854775808", "9223372036854775807"].repeat(40);
+        let parsed_input = parse_input(&line.join(" ")).unwrap();
+        assert!(predict(&parsed_input, Part::One).is_err());
+        assert!(predict_from_reader(line.join(" ").as_bytes(), Part::Two).is_err());
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz-fixtures/parse_input"
+        ));
+        check_solver(fixtures, &[EXAMPLE_INPUT], parse_input, |parsed| {
+            let _ = predict(parsed, Part::One);
+            let _ = predict(parsed, Part::Two);
+        });
+    }
+}